@@ -0,0 +1,268 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// The zeros of the decimal digits of unicode (Nd), every one followed by the digits 1 to 9 of its script.
+static DECIMAL_ZEROS: [u32; 77] = [
+    0x30, 0x660, 0x6F0, 0x7C0, 0x966, 0x9E6, 0xA66, 0xAE6, 0xB66, 0xBE6,
+    0xC66, 0xCE6, 0xD66, 0xDE6, 0xE50, 0xED0, 0xF20, 0x1040, 0x1090, 0x17E0,
+    0x1810, 0x1946, 0x19D0, 0x1A80, 0x1A90, 0x1B50, 0x1BB0, 0x1C40, 0x1C50, 0xA620,
+    0xA8D0, 0xA900, 0xA9D0, 0xA9F0, 0xAA50, 0xABF0, 0xFF10, 0x104A0, 0x10D30, 0x10D40,
+    0x11066, 0x110F0, 0x11136, 0x111D0, 0x112F0, 0x11450, 0x114D0, 0x11650, 0x116C0, 0x116D0,
+    0x116DA, 0x11730, 0x118E0, 0x11950, 0x11BF0, 0x11C50, 0x11D50, 0x11DA0, 0x11DE0, 0x11F50,
+    0x16130, 0x16A60, 0x16AC0, 0x16B50, 0x16D70, 0x1CCF0, 0x1D7CE, 0x1D7D8, 0x1D7E2, 0x1D7EC,
+    0x1D7F6, 0x1E140, 0x1E2F0, 0x1E4F0, 0x1E5F1, 0x1E950, 0x1FBF0,
+];
+
+/// The characters a CHAR class matches, stored as sorted ranges so the membership is a binary search.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CharSet {
+    ranges: Vec<(char, char)>,   // Sorted, disjoint and not adjacent
+    negated: bool,               // The set matches every character out of the ranges
+}
+
+impl CharSet {
+
+    pub fn new(mut ranges: Vec<(char, char)>, negated: bool) -> CharSet {
+        ranges.sort();
+        let mut merged = Vec::<(char, char)>::new();
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some(last) if start as u32 <= last.1 as u32 + 1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end))
+            }
+        }
+        CharSet{ranges: merged, negated}
+    }
+
+    /// The characters satisfying the predicate, among every unicode character.
+    pub fn from_predicate(predicate: impl Fn(char) -> bool) -> CharSet {
+        let mut ranges = Vec::<(char, char)>::new();
+        let mut previous: Option<char> = None;
+        for c in char::MIN..=char::MAX {
+            if predicate(c) {
+                match ranges.last_mut() {
+                    Some(last) if previous == Some(last.1) => last.1 = c,
+                    _ => ranges.push((c, c))
+                }
+            }
+            previous = Some(c);
+        }
+        CharSet{ranges, negated: false}
+    }
+
+    /// The set of a predefined macro: unicode decimal digits, unicode letters, or the other visible characters.
+    pub fn predefined(name: &str) -> Option<&'static CharSet> {
+        static DIGITS: OnceLock<CharSet> = OnceLock::new();
+        static LETTERS: OnceLock<CharSet> = OnceLock::new();
+        static OTHER: OnceLock<CharSet> = OnceLock::new();
+        match name {
+            "DIGITS" => Some(DIGITS.get_or_init(|| CharSet::from_predicate(is_decimal))),
+            "LETTERS" => Some(LETTERS.get_or_init(|| CharSet::from_predicate(char::is_alphabetic))),
+            "OTHER" => Some(OTHER.get_or_init(|| CharSet::from_predicate(|c| !c.is_alphanumeric() && !c.is_whitespace() && !c.is_control()))),
+            _ => None
+        }
+    }
+
+    /// Read the right side of a CHAR rule: strings like "a-z" "_" and macros like @LETTERS following each other,
+    /// a ^ before the first one negates the class. The macros are searched in the predefined ones, then in macros.
+    /// On error, return the offset in text where it was found and the message.
+    pub fn parse(text: &str, macros: &HashMap<&str, CharSet>) -> Result<CharSet, (usize, String)> {
+        let mut ranges = Vec::new();
+        let mut i = text.len() - text.trim_start().len();
+        let negated = text[i..].starts_with('^');
+        if negated {
+            i += 1;
+        }
+        loop {
+            i += text[i..].len() - text[i..].trim_start().len();
+            if i == text.len() {
+                break;
+            }
+            if let Some(rest) = text[i..].strip_prefix('@') {
+                let name = &rest[..rest.find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or(rest.len())];
+                match CharSet::predefined(name).or(macros.get(name)) {
+                    Some(set) => ranges.extend(set.matched_ranges()),
+                    None => return Err((i, format!("The macro @{name} doesn't exist")))
+                }
+                i += name.len() + 1;
+            } else if text[i..].starts_with('"') {
+                i = parse_string(text, i + 1, &mut ranges)?;
+            } else {
+                return Err((i, "A string or a macro was expected".to_string()))
+            }
+        }
+        Ok(CharSet::new(ranges, negated))
+    }
+
+    /// The characters matched by one of the sets.
+    pub fn union<'s>(sets: impl Iterator<Item = &'s CharSet>) -> CharSet {
+        CharSet::new(sets.flat_map(|set| set.matched_ranges()).collect(), false)
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        let found = self.ranges.binary_search_by(|&(start, end)| {
+            if end < c {
+                Ordering::Less
+            } else if start > c {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        }).is_ok();
+        found != self.negated
+    }
+
+    /// The ranges of the characters the set matches, computing the complement of a negated set.
+    fn matched_ranges(&self) -> Vec<(char, char)> {
+        if !self.negated {
+            return self.ranges.clone()
+        }
+        let mut res = Vec::new();
+        let mut start = Some(char::MIN);
+        for &(first, last) in self.ranges.iter() {
+            if let Some(start) = start.filter(|start| *start < first) {
+                res.push((start, before(first)));
+            }
+            start = after(last);
+        }
+        if let Some(start) = start {
+            res.push((start, char::MAX));
+        }
+        res
+    }
+
+    pub fn ranges(&self) -> &[(char, char)] {
+        &self.ranges
+    }
+
+    pub fn is_negated(&self) -> bool {
+        self.negated
+    }
+}
+
+/// Write the set back in the syntax of the CHAR rules.
+impl fmt::Display for CharSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negated {
+            write!(f, "^")?;
+        }
+        write!(f, "\"")?;
+        for &(start, end) in self.ranges.iter() {
+            write!(f, "{}", escape(start))?;
+            match end as u32 - start as u32 {
+                0 => (),
+                1 => write!(f, "{}", escape(end))?,
+                _ => write!(f, "-{}", escape(end))?
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
+/// The characters around c, skipping the surrogates which aren't characters.
+fn before(c: char) -> char {
+    match c {
+        '\u{e000}' => '\u{d7ff}',
+        _ => char::from_u32(c as u32 - 1).unwrap()
+    }
+}
+
+fn after(c: char) -> Option<char> {
+    match c {
+        '\u{d7ff}' => Some('\u{e000}'),
+        char::MAX => None,
+        _ => char::from_u32(c as u32 + 1)
+    }
+}
+
+fn escape(c: char) -> String {
+    match c {
+        '\n' => "\\n".to_string(),
+        '\t' => "\\t".to_string(),
+        '\r' => "\\r".to_string(),
+        '"' | '\\' | '-' => format!("\\{c}"),
+        _ if c.is_control() => format!("\\u{{{:x}}}", c as u32),
+        _ => c.to_string()
+    }
+}
+
+/// The text of a string literal written between quotes, with its escape sequences replaced.
+/// On error, return the offset in literal where it was found and the message.
+pub fn unescape(literal: &str) -> Result<String, (usize, String)> {
+    let mut res = String::new();
+    let mut i = 1;
+    while i < literal.len() - 1 {
+        res.push(parse_char(&literal[..literal.len() - 1], &mut i)?);
+    }
+    Ok(res)
+}
+
+/// Read the content of a string starting at i, just after its opening quote, and return the position after its closing quote.
+/// A - between two characters is a range, at the start or the end of the string it's the character itself.
+fn parse_string(text: &str, mut i: usize, ranges: &mut Vec<(char, char)>) -> Result<usize, (usize, String)> {
+    let opening = i - 1;
+    loop {
+        let start = i;
+        let c = match text[i..].chars().next() {
+            Some('"') => return Ok(i + 1),
+            Some(_) => parse_char(text, &mut i)?,
+            None => return Err((opening, "This string is never closed".to_string()))
+        };
+        if text[i..].starts_with('-') && i + 1 < text.len() && !text[i + 1..].starts_with('"') {
+            i += 1;
+            let end = parse_char(text, &mut i)?;
+            if end < c {
+                return Err((start, format!("The range {}-{} is reversed", escape(c), escape(end))))
+            }
+            ranges.push((c, end));
+        } else {
+            ranges.push((c, c));
+        }
+    }
+}
+
+/// Read the character at i, which may be an escape sequence, and move i after it.
+fn parse_char(text: &str, i: &mut usize) -> Result<char, (usize, String)> {
+    let start = *i;
+    let mut chars = text[start..].chars();
+    let c = chars.next().unwrap();
+    *i += c.len_utf8();
+    if c != '\\' {
+        return Ok(c)
+    }
+    let escaped = match chars.next() {
+        Some(escaped) => escaped,
+        None => return Err((start, "An escape sequence isn't finished".to_string()))
+    };
+    *i += escaped.len_utf8();
+    match escaped {
+        'n' => Ok('\n'),
+        't' => Ok('\t'),
+        'r' => Ok('\r'),
+        '0' => Ok('\0'),
+        '"' | '\\' | '\'' | '-' | '^' => Ok(escaped),
+        'u' => {
+            let code = text[*i..].strip_prefix('{').and_then(|rest| rest.find('}').map(|end| &rest[..end]));
+            let code = match code {
+                Some(code) => code,
+                None => return Err((start, "A unicode escape is written \\u{XXXX}".to_string()))
+            };
+            *i += code.len() + 2;
+            u32::from_str_radix(code, 16).ok()
+                .and_then(char::from_u32)
+                .ok_or((start, format!("\\u{{{code}}} isn't a valid unicode character")))
+        },
+        _ => Err((start, format!("Unknown escape sequence \\{escaped}")))
+    }
+}
+
+/// If c is a decimal digit of any script, like 7 or ٧, but not a number like ½, ² or Ⅻ.
+fn is_decimal(c: char) -> bool {
+    match DECIMAL_ZEROS.binary_search(&(c as u32)) {
+        Ok(_) => true,
+        Err(0) => false,
+        Err(i) => (c as u32) - DECIMAL_ZEROS[i - 1] < 10
+    }
+}
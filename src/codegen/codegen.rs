@@ -0,0 +1,262 @@
+use crate::engine::grammar::Grammar;
+use crate::engine::include::{Atom, Rule, texts, WHITESPACE, COMMENT};
+use crate::charset::CharSet;
+use crate::diagnostic::Diagnostic;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// The matching code shared by every generated lexer, the tables of the grammar are written before it.
+static RUNTIME: &str = include_str!("runtime.rs.in");
+
+/// Write a standalone Rust module containing a TokenKind enum and a Lexer iterator for the grammar.
+pub struct CodeGenerator<'g> {
+    grammar: &'g Grammar,
+    names: Vec<&'g str>,            // The name of every TPRIM, of every GROUP, of the kept trivia, then of every string
+    variants: Vec<String>,          // The TokenKind variant of every name
+    literals: Vec<usize>,           // The strings written in the group rules, which are tokens of their own
+    repeats: HashMap<usize, usize>, // Associate the repetitions used by the TPRIMs to their index in the generated table
+    out: String,
+}
+
+impl<'g> CodeGenerator<'g> {
+
+    pub fn new(grammar: &'g Grammar) -> Result<CodeGenerator<'g>, Diagnostic> {
+        let mut names = Vec::new();
+        let mut variants = Vec::<String>::new();
+        let trivia = if grammar.keep_trivia {vec!(WHITESPACE, COMMENT)} else {Vec::new()};
+        let names_of_productions = grammar.tprims.iter().chain(grammar.groups.iter()).map(|p| p.name.as_str());
+        for name in names_of_productions.chain(trivia) {
+            let variant = variant_name(name);
+            if !variant.starts_with(char::is_alphabetic) {
+                return Err(Diagnostic::message(format!("The token {name} can't be named in the generated lexer, a letter has to follow its underscores.")))
+            }
+            if variants.contains(&variant) {
+                return Err(Diagnostic::message(format!("Two tokens would be named {variant} in the generated lexer.")))
+            }
+            names.push(name);
+            variants.push(variant);
+        }
+        // A TPRIM can be named after the string it matches, like Lt = "<"
+        for literal in grammar.literals.iter() {
+            let mut variant = literal_variant(&literal.text);
+            if variants.contains(&variant) {
+                variant = format!("{}Literal", variant.trim_end_matches('_'));
+            }
+            if variants.contains(&variant) {
+                return Err(Diagnostic::message(format!("Two tokens would be named {variant} in the generated lexer.")))
+            }
+            names.push(&literal.name);
+            variants.push(variant);
+        }
+        let mut repeats = HashMap::new();
+        for production in grammar.tprims.iter() {
+            collect_repeats(grammar, &production.forest, &mut repeats);
+        }
+        let mut literals = Vec::new();
+        for production in grammar.groups.iter() {
+            collect_literals(grammar, &production.forest, &mut literals);
+        }
+        literals.sort_unstable();
+        literals.dedup();
+        Ok(CodeGenerator{grammar, names, variants, literals, repeats, out: String::new()})
+    }
+
+    pub fn generate(mut self, source: &str) -> String {
+        let _ = writeln!(self.out, "// Lexer generated by Toklang from {source}, do not edit it by hand.");
+        let _ = writeln!(self.out, "// The input is split in TPRIMs and in the strings of the group rules, the GROUP kinds are provided for the parsers built on top of it.");
+        self.write_kinds();
+        self.write_tables();
+        self.out.push_str(RUNTIME);
+        self.out
+    }
+
+    fn write_kinds(&mut self) {
+        let _ = writeln!(self.out, "\n#[allow(dead_code)]\n#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\npub enum TokenKind {{");
+        for variant in self.variants.iter() {
+            let _ = writeln!(self.out, "    {variant},");
+        }
+        let _ = writeln!(self.out, "}}\n\n#[allow(dead_code)]\nimpl TokenKind {{\n    pub fn name(self) -> &'static str {{\n        match self {{");
+        for (variant, name) in self.variants.iter().zip(self.names.iter()) {
+            let _ = writeln!(self.out, "            TokenKind::{variant} => {name:?},");
+        }
+        let _ = writeln!(self.out, "        }}\n    }}\n\n    pub fn is_group(self) -> bool {{");
+        if self.grammar.groups.is_empty() {
+            let _ = writeln!(self.out, "        false");
+        } else {
+            let groups = &self.variants[self.grammar.tprims.len()..self.grammar.tprims.len() + self.grammar.groups.len()];
+            let groups = groups.iter().map(|v| format!("TokenKind::{v}")).collect::<Vec<_>>();
+            let _ = writeln!(self.out, "        matches!(self, {})", groups.join(" | "));
+        }
+        let _ = writeln!(self.out, "    }}\n\n    pub fn is_trivia(self) -> bool {{");
+        let is_trivia = match self.grammar.keep_trivia {
+            true => format!("matches!(self, TokenKind::{} | TokenKind::{})", variant_name(WHITESPACE), variant_name(COMMENT)),
+            false => "false".to_string()
+        };
+        let _ = writeln!(self.out, "        {is_trivia}");
+        let _ = writeln!(self.out, "    }}\n}}");
+    }
+
+    fn write_tables(&mut self) {
+        let trivia = &self.grammar.trivia;
+        let _ = writeln!(self.out, "\nstatic TRIVIA: CharSet = {};", charset(&trivia.chars));
+        let _ = writeln!(self.out, "static LINE_COMMENTS: &[&str] = &{:?};", trivia.lines);
+        let blocks = trivia.blocks.iter().map(|b| format!("({:?}, {:?}, {})", b.open, b.close, b.nested)).collect::<Vec<_>>();
+        let _ = writeln!(self.out, "static BLOCK_COMMENTS: &[(&str, &str, bool)] = &[{}];", blocks.join(", "));
+        let kinds = match self.grammar.keep_trivia {
+            true => format!("Some((TokenKind::{}, TokenKind::{}))", variant_name(WHITESPACE), variant_name(COMMENT)),
+            false => "None".to_string()
+        };
+        let _ = writeln!(self.out, "// The kinds of the whitespaces and of the comments, if they are returned as tokens\nstatic TRIVIA_KINDS: Option<(TokenKind, TokenKind)> = {kinds};");
+        let _ = writeln!(self.out, "\nstatic LONGEST_MATCH: bool = {};", self.grammar.longest_match);
+        let _ = writeln!(self.out, "\nstatic CHARS: &[CharSet] = &[");
+        for class in self.grammar.chars.iter() {
+            let _ = writeln!(self.out, "    // {}\n    {},", class.name, charset(&class.set));
+        }
+        let _ = writeln!(self.out, "];");
+        let _ = writeln!(self.out, "\nstatic TPRIMS: &[Tprim] = &[");
+        for (i, production) in self.grammar.tprims.iter().enumerate() {
+            let _ = writeln!(self.out, "    // {}", production.name);
+            let _ = writeln!(self.out, "    Tprim {{\n        kind: TokenKind::{},\n        priority: {},\n        constraints: &{:?},\n        negated: {},", self.variants[i], self.grammar.priority(i), texts(&production.constraints), production.negated);
+            let _ = write!(self.out, "        rules: ");
+            self.write_rules(&production.forest, 2);
+            let _ = writeln!(self.out, ",\n    }},");
+        }
+        let _ = writeln!(self.out, "];");
+        let _ = writeln!(self.out, "\n// The strings of the group rules, tried before the TPRIMs\nstatic LITERAL_PRIORITY: usize = {};", self.grammar.literal_priority());
+        let _ = writeln!(self.out, "static LITERALS: &[(TokenKind, &str)] = &[");
+        let offset = self.variants.len() - self.grammar.literals.len();
+        for literal in self.literals.iter() {
+            let _ = writeln!(self.out, "    (TokenKind::{}, {:?}),", self.variants[offset + literal], self.grammar.literals[*literal].text);
+        }
+        let _ = writeln!(self.out, "];");
+        let mut repeats = self.repeats.iter().map(|(repeat, index)| (*index, *repeat)).collect::<Vec<_>>();
+        repeats.sort();
+        let _ = writeln!(self.out, "\nstatic REPEATS: &[Repeat] = &[");
+        for (_, repeat) in repeats {
+            let repetition = &self.grammar.repeats[repeat];
+            let max = repetition.max.map_or("usize::MAX".to_string(), |max| max.to_string());
+            let _ = write!(self.out, "    Repeat {{\n        min: {},\n        max: {max},\n        rules: ", repetition.min);
+            self.write_rules(&repetition.body, 2);
+            let _ = writeln!(self.out, ",\n    }},");
+        }
+        let _ = writeln!(self.out, "];");
+    }
+
+    fn write_rules(&mut self, rules: &[Rule], depth: usize) {
+        if rules.is_empty() {
+            let _ = write!(self.out, "&[]");
+            return
+        }
+        let indent = "    ".repeat(depth);
+        let _ = writeln!(self.out, "&[");
+        for rule in rules.iter() {
+            let atom = match rule.atom {
+                Atom::Char(class) => format!("Atom::Char({class})"),
+                Atom::Tprim(tprim) => format!("Atom::Tprim({tprim})"),
+                Atom::Repeat(repeat) => format!("Atom::Repeat({})", self.repeats[&repeat]),
+                Atom::Literal(literal) => format!("Atom::Literal({:?})", self.grammar.literals[literal].text),
+                Atom::Group(_) => unreachable!("A TPRIM can't contain a group")
+            };
+            let _ = write!(self.out, "{indent}    Rule {{ atom: {atom}, constraints: &{:?}, can_end: {}, next: ", texts(&rule.constraints), rule.can_end);
+            self.write_rules(&rule.next, depth + 1);
+            let _ = writeln!(self.out, " }},");
+        }
+        let _ = write!(self.out, "{indent}]");
+    }
+}
+
+/// Give an index to every repetition of the rules, and of the repetitions they contain.
+fn collect_repeats(grammar: &Grammar, rules: &[Rule], repeats: &mut HashMap<usize, usize>) {
+    for rule in rules.iter() {
+        if let Atom::Repeat(repeat) = rule.atom {
+            if !repeats.contains_key(&repeat) {
+                repeats.insert(repeat, repeats.len());
+                collect_repeats(grammar, &grammar.repeats[repeat].body, repeats);
+            }
+        }
+        collect_repeats(grammar, &rule.next, repeats);
+    }
+}
+
+/// Collect the strings of the rules, and of the repetitions they contain, which match something.
+fn collect_literals(grammar: &Grammar, rules: &[Rule], literals: &mut Vec<usize>) {
+    for rule in rules.iter() {
+        match rule.atom {
+            Atom::Literal(literal) if !grammar.literals[literal].text.is_empty() => literals.push(literal),
+            Atom::Repeat(repeat) => collect_literals(grammar, &grammar.repeats[repeat].body, literals),
+            _ => ()
+        }
+        collect_literals(grammar, &rule.next, literals);
+    }
+}
+
+/// The constructor of a set in the generated code.
+fn charset(set: &CharSet) -> String {
+    let ranges = set.ranges().iter().map(|r| format!("{r:?}")).collect::<Vec<_>>();
+    format!("CharSet {{ ranges: &[{}], negated: {} }}", ranges.join(", "), set.is_negated())
+}
+
+/// The name of a token as a Rust enum variant.
+fn variant_name(name: &str) -> String {
+    let mut res = String::new();
+    for part in name.split('_') {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            res.extend(first.to_uppercase());
+            res.extend(chars);
+        }
+    }
+    unreserved(ascii(res))
+}
+
+/// The name of a string as a Rust enum variant: its words capitalized and its symbols spelled, like AmpAmp for "&&".
+fn literal_variant(text: &str) -> String {
+    let mut res = String::new();
+    let mut in_word = false;
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            match in_word {
+                true => res.push(c),
+                false => res.extend(c.to_uppercase())
+            }
+            in_word = true;
+            continue
+        }
+        in_word = false;
+        let name = match c {
+            '(' => "LParen", ')' => "RParen", '{' => "LBrace", '}' => "RBrace", '[' => "LBracket", ']' => "RBracket",
+            '<' => "Lt", '>' => "Gt", '=' => "Eq", '!' => "Bang", '&' => "Amp", '|' => "Pipe", '+' => "Plus", '-' => "Minus",
+            '*' => "Star", '/' => "Slash", '\\' => "Backslash", '%' => "Percent", '^' => "Caret", '~' => "Tilde",
+            ',' => "Comma", ';' => "Semi", ':' => "Colon", '.' => "Dot", '?' => "Question", '#' => "Hash", '@' => "At",
+            '$' => "Dollar", '\'' => "Quote", '"' => "DoubleQuote", '`' => "Backtick", '_' => "Underscore",
+            ' ' => "Space", '\t' => "Tab", '\n' => "Newline", '\r' => "Return",
+            _ => {
+                let _ = write!(res, "U{:X}", c as u32);
+                continue
+            }
+        };
+        res.push_str(name);
+    }
+    // A string like "0x" would start the variant with a digit
+    if !res.starts_with(char::is_alphabetic) {
+        res.insert_str(0, "Literal");
+    }
+    unreserved(ascii(res))
+}
+
+/// The variant with its other characters than ASCII written by their code, like U3BB for λ: rustc warns about the
+/// identifiers mixing scripts, and the warning can only be allowed for the whole crate including the lexer.
+fn ascii(variant: String) -> String {
+    match variant.is_ascii() {
+        true => variant,
+        false => variant.chars().map(|c| if c.is_ascii() {c.to_string()} else {format!("U{:X}", c as u32)}).collect()
+    }
+}
+
+/// The variant, Self being written Self_: it's the only Rust keyword starting with a capital.
+fn unreserved(variant: String) -> String {
+    match variant == "Self" {
+        true => "Self_".to_string(),
+        false => variant
+    }
+}
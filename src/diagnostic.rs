@@ -0,0 +1,158 @@
+use std::fmt;
+
+/// Where a diagnostic points in the text it was built from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Position {
+    pub offset: usize,          // In bytes, from the start of the text
+    pub line: usize,            // Starting at 1
+    pub column: usize,          // In chars, starting at 1
+    pub grapheme: usize,        // In graphemes, starting at 1: the column seen by the reader
+    pub line_text: String,
+}
+
+impl Position {
+    pub fn new(text: &str, offset: usize) -> Position {
+        let mut offset = offset.min(text.len());
+        while !text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line_start = text[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = text[offset..].find('\n').map(|i| offset + i).unwrap_or(text.len());
+        Position {
+            offset,
+            line: text[..offset].matches('\n').count() + 1,
+            column: text[line_start..offset].chars().count() + 1,
+            grapheme: graphemes(&text[line_start..offset]).len() + 1,
+            line_text: text[line_start..line_end].trim_end_matches('\r').to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning
+}
+
+/// A message about a text, rendered like rustc does with the line it points to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub message: String,
+    pub position: Option<Box<Position>>,
+    pub path: Option<String>,   // The file the text comes from
+    pub help: Vec<String>,      // The lines of a hint to fix the problem
+}
+
+impl Diagnostic {
+
+    /// A diagnostic pointing at the offset of text.
+    pub fn new(text: &str, offset: usize, message: String) -> Diagnostic {
+        Diagnostic{level: Level::Error, message, position: Some(Box::new(Position::new(text, offset))), path: None, help: Vec::new()}
+    }
+
+    /// A diagnostic which isn't about a specific place of the text.
+    pub fn message(message: String) -> Diagnostic {
+        Diagnostic{level: Level::Error, message, position: None, path: None, help: Vec::new()}
+    }
+
+    /// Make the diagnostic a warning: the text can still be used.
+    pub fn warning(mut self) -> Diagnostic {
+        self.level = Level::Warning;
+        self
+    }
+
+    pub fn with_help(mut self, help: Vec<String>) -> Diagnostic {
+        self.help = help;
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.level == Level::Error
+    }
+
+    pub fn in_file(mut self, path: &str) -> Diagnostic {
+        self.path = Some(path.to_string());
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.level {
+            Level::Error => writeln!(f, "error: {}", self.message)?,
+            Level::Warning => writeln!(f, "warning: {}", self.message)?
+        }
+        let position = match &self.position {
+            Some(position) => position,
+            None => {
+                if let Some(path) = &self.path {
+                    write!(f, " --> {path}")?;
+                }
+                return self.write_help(f, "")
+            }
+        };
+        let number = position.line.to_string();
+        let margin = " ".repeat(number.len());
+        match &self.path {
+            Some(path) => writeln!(f, "{margin}--> {path}:{}:{}", position.line, position.column)?,
+            None => writeln!(f, "{margin}--> {}:{}", position.line, position.column)?
+        }
+        // The caret is aligned with the same tabs as the line, under the grapheme it points to
+        let caret_margin = graphemes(&position.line_text).into_iter()
+            .take(position.grapheme - 1)
+            .map(|g| match g.chars().next().unwrap() {
+                '\t' => "\t",
+                c if is_wide(c) => "  ",
+                _ => " "
+            })
+            .collect::<String>();
+        writeln!(f, "{margin} |")?;
+        writeln!(f, "{number} | {}", position.line_text)?;
+        write!(f, "{margin} | {caret_margin}^")?;
+        self.write_help(f, &margin)
+    }
+}
+
+impl Diagnostic {
+    fn write_help(&self, f: &mut fmt::Formatter<'_>, margin: &str) -> fmt::Result {
+        for (i, line) in self.help.iter().enumerate() {
+            match i {
+                0 => write!(f, "\n{margin} = help: {line}")?,
+                _ => write!(f, "\n{margin}         {line}")?
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The graphemes of the text, approximated by grouping every character with the combining marks, the variation
+/// selectors and the skin tones following it, and with the character following a zero width joiner.
+pub fn graphemes(text: &str) -> Vec<&str> {
+    let mut res = Vec::new();
+    let mut start = 0;
+    let mut joined = false;
+    for (i, c) in text.char_indices() {
+        if i > 0 && !joined && !extends_grapheme(c) {
+            res.push(&text[start..i]);
+            start = i;
+        }
+        joined = c == '\u{200d}';
+    }
+    if start < text.len() {
+        res.push(&text[start..]);
+    }
+    res
+}
+
+fn extends_grapheme(c: char) -> bool {
+    matches!(c, '\u{300}'..='\u{36f}' | '\u{1ab0}'..='\u{1aff}' | '\u{1dc0}'..='\u{1dff}' | '\u{20d0}'..='\u{20ff}'
+        | '\u{fe00}'..='\u{fe0f}' | '\u{fe20}'..='\u{fe2f}' | '\u{200d}' | '\u{1f3fb}'..='\u{1f3ff}' | '\u{e0100}'..='\u{e01ef}')
+}
+
+/// If the character takes two columns in a terminal: the CJK characters, the hangul and the emojis.
+fn is_wide(c: char) -> bool {
+    matches!(c, '\u{1100}'..='\u{115f}' | '\u{2e80}'..='\u{a4cf}' | '\u{ac00}'..='\u{d7a3}' | '\u{f900}'..='\u{faff}'
+        | '\u{fe30}'..='\u{fe4f}' | '\u{ff00}'..='\u{ff60}' | '\u{ffe0}'..='\u{ffe6}' | '\u{1f300}'..='\u{1f64f}'
+        | '\u{1f900}'..='\u{1f9ff}' | '\u{20000}'..='\u{3fffd}')
+}
@@ -0,0 +1,526 @@
+use super::include::*;
+use super::grammar::Grammar;
+use crate::diagnostic::Diagnostic;
+use crate::error::Error;
+use std::collections::{HashSet, VecDeque};
+
+/// The rank of the first lexeme a rule would push, and the atom matching it.
+type Lead = ((usize, usize), Atom);
+
+/// Execute an interpreted grammar on an input text.
+pub struct Engine<'g> {
+    grammar: &'g Grammar,
+    text: &'g str,
+    lexemes: Vec<Lexeme<'g>>,
+    tprim_memo: HashMap<(usize, usize), Vec<usize>>,    // Associate a TPRIM and a position to every end of its matches
+    active: HashSet<(Atom, usize)>,                     // The rules being matched, to stop on left recursion
+    furthest: usize,                                    // The furthest position where a TPRIM was expected
+    expected: Vec<String>,                              // The TPRIMs and the strings expected at this position
+    after: Option<Lexeme<'g>>,                          // The last lexeme accepted before this position
+    previous: Option<Lexeme<'g>>,                       // The last TPRIM or string of the lexemes already taken
+    unclosed: Option<usize>,                            // The first block comment found without its end
+    ambiguity: Option<Diagnostic>,                      // The first place where two TPRIMs tied to start a rule
+}
+
+impl<'g> Engine<'g> {
+
+    pub fn new(grammar: &'g Grammar, text: &'g str) -> Engine<'g> {
+        Engine {
+            grammar,
+            text,
+            lexemes: Vec::new(),
+            tprim_memo: HashMap::new(),
+            active: HashSet::new(),
+            furthest: 0,
+            expected: Vec::new(),
+            after: None,
+            previous: None,
+            unclosed: None,
+            ambiguity: None,
+        }
+    }
+
+    /// Match the item of the text starting at i: the first group if the grammar has groups, a TPRIM otherwise.
+    /// Return the position of the next item, after the trivia.
+    fn next_item(&mut self, i: usize) -> Result<usize, Diagnostic> {
+        let end = match self.grammar.start() {
+            Some(group) => self.parse_group(group, i),
+            None => self.next_lexeme(i)?
+        };
+        match (end, self.ambiguity.take()) {
+            (_, Some(ambiguity)) => Err(ambiguity),
+            (Some(end), None) if end > i => {
+                // The positions before the item are never matched again
+                self.tprim_memo.retain(|(_, j), _| *j >= end);
+                Ok(self.skip_trivia(end))
+            },
+            _ => Err(self.error())
+        }
+    }
+
+    /// The lexemes pushed since the last call, they can't be taken back once their item is matched.
+    fn take(&mut self) -> Vec<Lexeme<'g>> {
+        let lexemes = std::mem::take(&mut self.lexemes);
+        if let Some(last) = lexemes.iter().rev().find(|lexeme| matches!(lexeme.kind, LexemeKind::Tprim | LexemeKind::Literal)) {
+            self.previous = Some(last.clone());
+        }
+        lexemes
+    }
+
+    /// Match the whole text with only one lexeme of the group. A text only made of trivia gives no lexeme.
+    pub fn tokenize_one(mut self, group: usize) -> Result<Vec<Lexeme<'g>>, Diagnostic> {
+        let i = self.skip_trivia(0);
+        if i == self.text.len() {
+            return Ok(self.lexemes)
+        }
+        match self.parse_group(group, i) {
+            Some(end) if self.skip_trivia(end) == self.text.len() => self.finish(),
+            _ => Err(self.error())
+        }
+    }
+
+    /// The lexemes of the whole text, unless a tie between two TPRIMs was found on the way.
+    fn finish(self) -> Result<Vec<Lexeme<'g>>, Diagnostic> {
+        match self.ambiguity {
+            Some(ambiguity) => Err(ambiguity),
+            None => Ok(self.lexemes)
+        }
+    }
+
+    fn error(&self) -> Diagnostic {
+        if let Some(ambiguity) = &self.ambiguity {
+            return ambiguity.clone()
+        }
+        if let Some(i) = self.unclosed.filter(|i| *i <= self.furthest) {
+            let block = self.grammar.trivia.unclosed(self.text, i).unwrap();
+            return Diagnostic::new(self.text, i, format!("This comment is never closed by {:?}", block.close))
+        }
+        let found = match self.text[self.furthest..].chars().next() {
+            Some(c) => format!("'{c}'"),
+            None => "end of input".to_string()
+        };
+        let after = match &self.after {
+            Some(lexeme) if lexeme.kind == LexemeKind::Literal => format!(" after {}", lexeme.name),
+            Some(lexeme) => format!(" after {} `{}`", lexeme.name, lexeme.text(self.text)),
+            None => String::new()
+        };
+        let message = if self.expected.is_empty() {
+            format!("Unexpected {found}{after}")
+        } else {
+            format!("Unexpected {found}, expected {}{after}", self.expected.join(" or "))
+        };
+        Diagnostic::new(self.text, self.furthest, message)
+    }
+
+    /// The position of the next lexeme, the trivia before it being pushed if the grammar keeps them.
+    fn skip_trivia(&mut self, mut i: usize) -> usize {
+        while let Some((end, name)) = self.grammar.trivia.next(self.text, i) {
+            if self.grammar.keep_trivia {
+                self.lexemes.push(Lexeme::new(name, LexemeKind::Trivia, (i, end)));
+            }
+            i = end;
+        }
+        if self.unclosed.is_none() && self.grammar.trivia.unclosed(self.text, i).is_some() {
+            self.unclosed = Some(i);
+        }
+        i
+    }
+
+    fn expect(&mut self, i: usize, name: &str) {
+        if i > self.furthest {
+            self.furthest = i;
+            self.expected.clear();
+            // The lexemes of a group rejected at i may still be there, they start at i
+            let before = |lexeme: &&Lexeme| lexeme.content.1 <= i && matches!(lexeme.kind, LexemeKind::Tprim | LexemeKind::Literal);
+            self.after = self.lexemes.iter().rev().find(before).or(self.previous.as_ref()).cloned();
+        }
+        if i == self.furthest && !self.expected.iter().any(|expected| expected == name) {
+            self.expected.push(name.to_string());
+        }
+    }
+
+    /// Push the TPRIM of greatest rank starting at i, used when the grammar has no group.
+    /// Two TPRIMs of the same rank matching at i make the input ambiguous.
+    fn next_lexeme(&mut self, i: usize) -> Result<Option<usize>, Diagnostic> {
+        let mut best: Option<(usize, usize)> = None;
+        let mut tie = None;     // A TPRIM matching with the rank of the best one
+        for tprim in 0..self.grammar.tprims.len() {
+            let end = match self.lex(tprim, i) {
+                Some(end) => end,
+                None => {
+                    self.expect(i, &self.grammar.tprims[tprim].name);
+                    continue;
+                }
+            };
+            let rank = self.grammar.rank(tprim, i, end);
+            match best {
+                Some((b, b_end)) if self.grammar.rank(b, i, b_end) > rank => (),
+                Some((b, b_end)) if self.grammar.rank(b, i, b_end) == rank => tie = tie.or(Some(tprim)),
+                _ => {
+                    best = Some((tprim, end));
+                    tie = None;
+                }
+            }
+        }
+        let (tprim, end) = match best {
+            Some(best) => best,
+            None => return Ok(None)
+        };
+        if let Some(other) = tie {
+            return Err(self.ambiguous(i, tprim, other))
+        }
+        self.lexemes.push(Lexeme::new(&self.grammar.tprims[tprim].name, LexemeKind::Tprim, (i, end)));
+        Ok(Some(end))
+    }
+
+    /// The error of two TPRIMs matching at i with the same rank.
+    fn ambiguous(&self, i: usize, tprim: usize, other: usize) -> Diagnostic {
+        let (name, other) = (&self.grammar.tprims[tprim].name, &self.grammar.tprims[other].name);
+        let length = if self.grammar.longest_match {" as long"} else {""};
+        Diagnostic::new(self.text, i, format!("The input is ambiguous, {name} and {other} both match here{length} with the same priority"))
+            .with_help(vec!(format!("give {name} and {other} different priorities in the #PRIORITIES section")))
+    }
+
+    /// The end of the longest match of the TPRIM starting at i, if it satisfies its constraints.
+    fn lex(&mut self, tprim: usize, i: usize) -> Option<usize> {
+        let end = *self.match_tprim(tprim, i).iter().max()?;
+        let production = &self.grammar.tprims[tprim];
+        if end > i && production.constraint_satisfied(&self.text[i..end], &[]) {
+            Some(end)
+        } else {
+            None
+        }
+    }
+
+    fn match_tprim(&mut self, tprim: usize, i: usize) -> Vec<usize> {
+        if let Some(ends) = self.tprim_memo.get(&(tprim, i)) {
+            return ends.clone()
+        }
+        if !self.active.insert((Atom::Tprim(tprim), i)) {
+            return Vec::new()
+        }
+        let mut ends = Vec::new();
+        self.match_chars(&self.grammar.tprims[tprim].forest, i, &mut ends);
+        self.active.remove(&(Atom::Tprim(tprim), i));
+        ends.sort();
+        ends.dedup();
+        self.tprim_memo.insert((tprim, i), ends.clone());
+        ends
+    }
+
+    /// Push in ends every position where a match of the forest starting at i can stop.
+    fn match_chars(&mut self, forest: &'g [Rule], i: usize, ends: &mut Vec<usize>) {
+        for rule in forest.iter() {
+            let after = match rule.atom {
+                Atom::Char(class) => match self.text[i..].chars().next() {
+                    Some(c) if self.grammar.chars[class].contains(c) => vec!(i + c.len_utf8()),
+                    _ => Vec::new()
+                },
+                Atom::Tprim(tprim) => self.match_tprim(tprim, i),
+                Atom::Repeat(repeat) => self.match_repeat(repeat, i),
+                Atom::Literal(literal) => self.match_literal(literal, i).into_iter().collect(),
+                Atom::Group(_) => unreachable!("A TPRIM can't contain a group")
+            };
+            for j in after {
+                if !rule.constraint_satisfied(&self.text[i..j], &[]) {
+                    continue;
+                }
+                if rule.is_leaf() || rule.can_end {
+                    ends.push(j);
+                }
+                self.match_chars(&rule.next, j, ends);
+            }
+        }
+    }
+
+    /// The end of the string if the text contains it at i.
+    fn match_literal(&self, literal: usize, i: usize) -> Option<usize> {
+        let text = &self.grammar.literals[literal].text;
+        self.text[i..].starts_with(text.as_str()).then(|| i + text.len())
+    }
+
+    /// Every position where the repetition starting at i can stop. The body is matched once more at each turn of the loop
+    /// from the positions reached by the previous turn.
+    fn match_repeat(&mut self, repeat: usize, i: usize) -> Vec<usize> {
+        let repetition = &self.grammar.repeats[repeat];
+        let mut ends = Vec::new();
+        if repetition.min == 0 {
+            ends.push(i);
+        }
+        let mut reached = HashSet::new();   // The positions reached after min matches or more, looping on them again is useless
+        let mut positions = vec!(i);
+        let mut count = 0;
+        while !positions.is_empty() && repetition.can_repeat(count) {
+            count += 1;
+            let mut next = Vec::new();
+            for start in positions {
+                let mut found = Vec::new();
+                self.match_chars(&repetition.body, start, &mut found);
+                next.extend(found.into_iter().filter(|j| *j > start));
+            }
+            next.sort();
+            next.dedup();
+            if count >= repetition.min {
+                next.retain(|j| reached.insert(*j));
+                ends.extend(next.iter().copied());
+            }
+            positions = next;
+        }
+        ends
+    }
+
+    /// The rank of the first lexeme the rule would push at i and its atom, None if it can't start here.
+    fn lead(&mut self, rule: &'g Rule, i: usize) -> Option<Lead> {
+        match rule.atom {
+            Atom::Tprim(tprim) => {
+                let end = self.lex(tprim, i);
+                match end {
+                    Some(end) if rule.constraint_satisfied(&self.text[i..end], &[]) => {
+                        let (length, priority) = self.grammar.rank(tprim, i, end);
+                        Some(((length, priority + 1), rule.atom))
+                    },
+                    // A TPRIM constrained by strings expects these strings
+                    _ => {
+                        let strings = texts(&rule.constraints);
+                        if strings.is_empty() {
+                            self.expect(i, &self.grammar.tprims[tprim].name);
+                        }
+                        for string in strings {
+                            self.expect(i, &format!("{string:?}"));
+                        }
+                        None
+                    }
+                }
+            },
+            Atom::Literal(literal) => match self.match_literal(literal, i) {
+                Some(end) => match self.grammar.longest_match {
+                    true => Some(((end - i, self.grammar.literal_priority()), rule.atom)),
+                    false => Some(((0, self.grammar.literal_priority()), rule.atom))
+                },
+                None => {
+                    self.expect(i, &self.grammar.literals[literal].name);
+                    None
+                }
+            },
+            Atom::Group(group) => {
+                if !self.active.insert((rule.atom, i)) {
+                    return None
+                }
+                let mut res = None;
+                for son in self.grammar.groups[group].forest.iter() {
+                    res = greatest(res, self.lead(son, i));
+                }
+                self.active.remove(&(rule.atom, i));
+                res
+            },
+            Atom::Repeat(repeat) => {
+                let mut res = None;
+                for son in self.grammar.repeats[repeat].body.iter() {
+                    res = greatest(res, self.lead(son, i));
+                }
+                // A repetition which can be skipped is tried last, the priorities of the TPRIMs being shifted above it
+                if self.grammar.repeats[repeat].min == 0 {
+                    res = res.or(Some(((0, 0), rule.atom)));
+                }
+                res
+            },
+            Atom::Char(_) => unreachable!("A group can't contain a CHAR")
+        }
+    }
+
+    /// Match one of the rules of the forest starting at i, trying first the rules whose first lexeme has the greatest rank.
+    /// When another rule starting with a different TPRIM of the same rank would match too, the input is ambiguous.
+    /// Return the end of the last pushed lexeme.
+    fn parse_forest(&mut self, forest: &'g [Rule], i: usize) -> Option<usize> {
+        let start = self.lexemes.len();
+        let i = self.skip_trivia(i);
+        let mut candidates = Vec::new();
+        for rule in forest.iter() {
+            if let Some(lead) = self.lead(rule, i) {
+                candidates.push((lead, rule));
+            }
+        }
+        candidates.sort_by(|((r1, _), _), ((r2, _), _)| r2.cmp(r1));
+        for (index, ((rank, atom), rule)) in candidates.iter().enumerate() {
+            let last = match self.parse_rule(rule, i) {
+                Some(last) => last,
+                None => continue
+            };
+            if let (Atom::Tprim(tprim), None) = (atom, &self.ambiguity) {
+                let tie = candidates[index + 1..].iter().find_map(|((r, other), rule)| match other {
+                    Atom::Tprim(other) if r == rank && other != tprim && self.probe(rule, i) => Some(*other),
+                    _ => None
+                });
+                if let Some(other) = tie {
+                    self.ambiguity = Some(self.ambiguous(i, *tprim, other));
+                }
+            }
+            return Some(last)
+        }
+        self.lexemes.truncate(start);
+        None
+    }
+
+    /// Match the rule starting at i, its first atom then the rules following it. Return the end of the last pushed lexeme.
+    fn parse_rule(&mut self, rule: &'g Rule, i: usize) -> Option<usize> {
+        let mark = self.lexemes.len();
+        // The ends of the atom with the number of lexemes pushed until each one, the preferred first
+        let ends = match rule.atom {
+            Atom::Tprim(tprim) => {
+                let end = self.lex(tprim, i).unwrap();
+                self.lexemes.push(Lexeme::new(&self.grammar.tprims[tprim].name, LexemeKind::Tprim, (i, end)));
+                vec!((end, self.lexemes.len()))
+            },
+            Atom::Literal(literal) => {
+                let end = self.match_literal(literal, i).unwrap();
+                self.lexemes.push(Lexeme::new(&self.grammar.literals[literal].name, LexemeKind::Literal, (i, end)));
+                vec!((end, self.lexemes.len()))
+            },
+            Atom::Group(group) => self.parse_group(group, i).map(|end| (end, self.lexemes.len())).into_iter().collect(),
+            Atom::Repeat(repeat) => self.parse_repeat(repeat, i),
+            Atom::Char(_) => unreachable!("A group can't contain a CHAR")
+        };
+        for (end, pushed) in ends {
+            self.lexemes.truncate(pushed);
+            if !rule.constraint_satisfied(&self.text[i..end], &self.lexemes[mark..]) {
+                // A constrained group expects its strings and its TPRIMs
+                for constraint in rule.constraints.iter() {
+                    match constraint {
+                        Constraint::Text(text) => self.expect(i, &format!("{text:?}")),
+                        Constraint::Contains(tprim) => self.expect(i, tprim)
+                    }
+                }
+                continue
+            }
+            if rule.is_leaf() {
+                return Some(end)
+            }
+            if let Some(last) = self.parse_forest(&rule.next, end) {
+                return Some(last)
+            }
+            if rule.can_end {
+                return Some(end)
+            }
+        }
+        self.lexemes.truncate(mark);
+        None
+    }
+
+    /// If the rule would match at i. Nothing is kept of the attempt, neither its lexemes nor what it found.
+    fn probe(&mut self, rule: &'g Rule, i: usize) -> bool {
+        let mark = self.lexemes.len();
+        let state = (self.furthest, std::mem::take(&mut self.expected), self.after.clone(), self.unclosed, self.ambiguity.clone());
+        let matched = self.parse_rule(rule, i).is_some();
+        self.lexemes.truncate(mark);
+        (self.furthest, self.expected, self.after, self.unclosed, self.ambiguity) = state;
+        matched
+    }
+
+    /// Match the body of the repetition as many times as possible. Return every end reached after min matches or more,
+    /// with the number of lexemes pushed until it, the furthest first: the rules following the repetition can take back
+    /// its last matches, like in a TPRIM.
+    fn parse_repeat(&mut self, repeat: usize, i: usize) -> Vec<(usize, usize)> {
+        let repetition = &self.grammar.repeats[repeat];
+        let mark = self.lexemes.len();
+        let mut ends = Vec::new();
+        let mut end = i;
+        let mut count = 0;
+        loop {
+            if count >= repetition.min {
+                ends.push((end, self.lexemes.len()));
+            }
+            if !repetition.can_repeat(count) {
+                break;
+            }
+            let before = self.lexemes.len();
+            match self.parse_forest(&repetition.body, end) {
+                Some(next) if next > end => end = next,
+                _ => {
+                    self.lexemes.truncate(before);
+                    break;
+                }
+            }
+            count += 1;
+        }
+        if ends.is_empty() {
+            self.lexemes.truncate(mark);
+        }
+        ends.reverse();
+        ends
+    }
+
+    fn parse_group(&mut self, group: usize, i: usize) -> Option<usize> {
+        let start = self.lexemes.len();
+        let i = self.skip_trivia(i);
+        if !self.active.insert((Atom::Group(group), i)) {
+            self.lexemes.truncate(start);
+            return None
+        }
+        let mark = self.lexemes.len();
+        self.lexemes.push(Lexeme::new(&self.grammar.groups[group].name, LexemeKind::Group, (i, i)));
+        let production = &self.grammar.groups[group];
+        let mut end = self.parse_forest(&production.forest, i);
+        if end.is_some_and(|end| !production.constraint_satisfied(&self.text[i..end], &self.lexemes[mark..])) {
+            self.expect(i, &production.name);
+            end = None;
+        }
+        self.active.remove(&(Atom::Group(group), i));
+        match end {
+            Some(end) => {
+                self.lexemes[mark].content.1 = end;
+                self.lexemes[mark].size = self.lexemes.len() - mark - 1;
+            },
+            None => self.lexemes.truncate(start)
+        }
+        end
+    }
+}
+
+/// The lead of greatest rank, the first one on a tie.
+fn greatest(a: Option<Lead>, b: Option<Lead>) -> Option<Lead> {
+    match (a, b) {
+        (Some(a), Some(b)) if b.0 > a.0 => Some(b),
+        (None, b) => b,
+        (a, _) => a
+    }
+}
+
+/// The lexemes of a text, matched while they are pulled. If the grammar has groups, the text has to be a sequence
+/// of the first one, otherwise a sequence of TPRIMs: the lexemes of an item come once it is wholly matched.
+/// The iteration stops after the first error.
+pub struct Lexemes<'g> {
+    engine: Engine<'g>,
+    next: Option<usize>,                // The position of the next item, none after an error
+    ready: VecDeque<Lexeme<'g>>,        // The lexemes of the items matched but not pulled yet
+}
+
+impl<'g> Lexemes<'g> {
+
+    pub fn new(grammar: &'g Grammar, text: &'g str) -> Lexemes<'g> {
+        let mut engine = Engine::new(grammar, text);
+        let next = Some(engine.skip_trivia(0));
+        let ready = engine.take().into();
+        Lexemes{engine, next, ready}
+    }
+}
+
+impl<'g> Iterator for Lexemes<'g> {
+    type Item = Result<Lexeme<'g>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.ready.is_empty() {
+            let i = self.next.filter(|i| *i < self.engine.text.len())?;
+            match self.engine.next_item(i) {
+                Ok(next) => {
+                    self.next = Some(next);
+                    self.ready.extend(self.engine.take());
+                },
+                Err(diagnostic) => {
+                    self.next = None;
+                    return Some(Err(Error::Input(diagnostic)))
+                }
+            }
+        }
+        self.ready.pop_front().map(Ok)
+    }
+}
@@ -0,0 +1,284 @@
+use super::include::*;
+use super::engine::Lexemes;
+use super::tree::Tree;
+use crate::error::Error;
+use crate::diagnostic::Diagnostic;
+use crate::interpreteur::interpreteur::Interpreteur;
+use crate::interpreteur::include::{Forest, Identity, Node};
+use crate::interpreteur::validation::validate;
+use crate::charset::unescape;
+use crate::symbols::{SymbolTable, SymbolClass, TokenKind};
+use crate::tokenizer::include::Token;
+use std::sync::Arc;
+use std::sync::mpsc::{sync_channel, Receiver};
+
+/// The interpreted grammar, with every name resolved to an index so it can be executed by the engine.
+#[derive(Debug)]
+pub struct Grammar {
+    pub(crate) chars: Vec<CharClass>,
+    pub(crate) tprims: Vec<Production>,
+    pub(crate) groups: Vec<Production>,
+    pub(crate) repeats: Vec<Repetition>,
+    pub(crate) literals: Vec<Literal>,
+    pub(crate) priorities: Vec<usize>,          // The priority of every TPRIM
+    pub(crate) longest_match: bool,             // The longest match wins before the greatest priority
+    pub(crate) trivia: Trivia,
+    pub(crate) keep_trivia: bool,               // The trivia are pushed as lexemes
+    pub(crate) symbols: SymbolTable,            // The token kinds of the declared names, of the strings and of the kept trivia
+    pub warnings: Vec<Diagnostic>,              // What the validation found suspicious in the grammar
+}
+
+impl Grammar {
+
+    pub(crate) fn new(interp: &Interpreteur) -> Result<Grammar, Diagnostic> {
+        let mut atoms = HashMap::<&str, Atom>::new();
+        let mut grammar = Grammar{chars: Vec::new(), tprims: Vec::new(), groups: Vec::new(), repeats: Vec::new(), literals: Vec::new(), priorities: Vec::new(), longest_match: interp.longest_match(), trivia: Trivia::default(), keep_trivia: interp.keep_trivia(), symbols: interp.symbols().clone(), warnings: Vec::new()};
+        for (_, name, class) in interp.symbols().iter() {
+            match class {
+                SymbolClass::Char => {
+                    atoms.insert(name, Atom::Char(grammar.chars.len()));
+                    grammar.chars.push(CharClass{name: name.to_string(), set: interp.symb_types()[name].clone().unwrap_or_default()});
+                },
+                SymbolClass::Tprim => {
+                    atoms.insert(name, Atom::Tprim(grammar.tprims.len()));
+                    grammar.tprims.push(Production{name: name.to_string(), forest: Vec::new(), constraints: Vec::new(), negated: false});
+                },
+                _ => {
+                    atoms.insert(name, Atom::Group(grammar.groups.len()));
+                    grammar.groups.push(Production{name: name.to_string(), forest: Vec::new(), constraints: Vec::new(), negated: false});
+                }
+            }
+        }
+        // The TPRIMs listed in the #PRIORITIES section win over the other ones, which rank below them in declaration order
+        let unlisted = grammar.tprims.iter().filter(|production| !interp.priorities().contains_key(production.name.as_str())).count();
+        let mut next = unlisted;
+        for production in grammar.tprims.iter() {
+            grammar.priorities.push(match interp.priorities().get(production.name.as_str()) {
+                Some(priority) => priority + unlisted + 1,
+                None => {
+                    next -= 1;
+                    next + 1
+                }
+            });
+        }
+        for production in grammar.tprims.iter_mut() {
+            let identity = &interp.token_types()[production.name.as_str()];
+            production.forest = compile_forest(interp, &atoms, &mut grammar.repeats, &mut grammar.literals, identity, identity.forest(), false)?;
+            production.constraints = compile_constraints(interp, identity.name(), identity.constraints())?;
+            production.negated = identity.is_negated();
+        }
+        for production in grammar.groups.iter_mut() {
+            let identity = &interp.group_types()[production.name.as_str()];
+            production.forest = compile_forest(interp, &atoms, &mut grammar.repeats, &mut grammar.literals, identity, identity.forest(), true)?;
+            production.constraints = compile_constraints(interp, identity.name(), identity.constraints())?;
+            production.negated = identity.is_negated();
+        }
+        grammar.trivia = compile_trivia(interp, &grammar)?;
+        for literal in grammar.literals.iter() {
+            grammar.symbols.intern(&literal.name, SymbolClass::Literal);
+        }
+        if grammar.keep_trivia {
+            grammar.symbols.intern(WHITESPACE, SymbolClass::Trivia);
+            grammar.symbols.intern(COMMENT, SymbolClass::Trivia);
+        }
+        Ok(grammar)
+    }
+
+    /// Load the grammar described by the text of a .tl file.
+    pub fn parse(text: &str) -> Result<Grammar, Error> {
+        Grammar::load(&crate::interpret(text)?)
+    }
+
+    /// Load the grammar without checking the syntax of its lines: the meta-grammar describing this syntax.
+    pub(crate) fn bootstrap(text: &str) -> Result<Grammar, Error> {
+        Grammar::load(&crate::bootstrap(text)?)
+    }
+
+    fn load(interp: &Interpreteur) -> Result<Grammar, Error> {
+        let diagnostics = validate(interp);
+        if diagnostics.iter().any(|d| d.is_error()) {
+            return Err(Error::Grammar(diagnostics))
+        }
+        let mut grammar = Grammar::new(interp).map_err(|e| Error::Grammar(vec!(e)))?;
+        grammar.warnings = diagnostics;
+        Ok(grammar)
+    }
+
+    /// Load the grammar described by the .tl file at path.
+    pub fn from_path(path: &str) -> Result<Grammar, Error> {
+        let text = std::fs::read_to_string(path).map_err(|e| Error::Io(path.to_string(), e))?;
+        let mut grammar = Grammar::parse(&text).map_err(|e| e.in_file(path))?;
+        grammar.warnings = grammar.warnings.into_iter().map(|w| w.in_file(path)).collect();
+        Ok(grammar)
+    }
+
+    /// The names of the declared CHARS.
+    pub fn char_names(&self) -> impl Iterator<Item = &str> {
+        self.chars.iter().map(|c| c.name.as_str())
+    }
+
+    /// The names of the declared TPRIMS.
+    pub fn tprim_names(&self) -> impl Iterator<Item = &str> {
+        self.tprims.iter().map(|p| p.name.as_str())
+    }
+
+    /// The names of the declared GROUPS.
+    pub fn group_names(&self) -> impl Iterator<Item = &str> {
+        self.groups.iter().map(|p| p.name.as_str())
+    }
+
+    /// The characters of a CHAR class.
+    pub fn char_set(&self, name: &str) -> Option<&CharSet> {
+        self.chars.iter().find(|c| c.name == name).map(|c| &c.set)
+    }
+
+    /// Split the input in lexemes, matched while they are pulled, see Lexemes.
+    pub fn lexemes<'g>(&'g self, input: &'g str) -> Lexemes<'g> {
+        Lexemes::new(self, input)
+    }
+
+    /// Split the whole input in lexemes, or give its first error.
+    pub fn tokenize<'g>(&'g self, input: &'g str) -> Result<Vec<Lexeme<'g>>, Error> {
+        self.lexemes(input).collect()
+    }
+
+    /// Split the input in tokens, matched while they are pulled, the kind of every lexeme being interned in the
+    /// symbols of the grammar.
+    pub fn tokens<'g>(&'g self, input: &'g str) -> impl Iterator<Item = Result<Token, Error>> + 'g {
+        self.lexemes(input).map(|lexeme| lexeme.map(|lexeme| Token::new(self.symbols.get(lexeme.name).unwrap(), lexeme.content)))
+    }
+
+    /// Split the input in tokens in another thread, a few tokens being matched in advance of those received.
+    /// The thread stops after the first error, or when the receiver is dropped.
+    pub fn spawn(grammar: Arc<Grammar>, input: String) -> Receiver<Result<Token, Error>> {
+        let (sender, receiver) = sync_channel(SPAWN_BOUND);
+        std::thread::spawn(move || {
+            for token in grammar.tokens(&input) {
+                if sender.send(token).is_err() {
+                    break;
+                }
+            }
+        });
+        receiver
+    }
+
+    /// The token kind of a declared name, of a string written in a rule with its quotes, or of a kept trivia.
+    pub fn kind(&self, name: &str) -> Option<TokenKind> {
+        self.symbols.get(name)
+    }
+
+    /// The token kinds of the grammar, see Grammar::kind.
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// Split the input in lexemes and nest them in the groups containing them.
+    pub fn tree<'g>(&'g self, input: &'g str) -> Result<Vec<Tree<'g>>, Error> {
+        Ok(Tree::build(&self.tokenize(input)?))
+    }
+
+    /// The group the input is made of: the first declared one.
+    pub(crate) fn start(&self) -> Option<usize> {
+        if self.groups.is_empty() {
+            None
+        } else {
+            Some(0)
+        }
+    }
+
+    /// The index of a declared GROUP.
+    pub(crate) fn group(&self, name: &str) -> Option<usize> {
+        self.groups.iter().position(|production| production.name == name)
+    }
+
+    /// The priority of a TPRIM when several of them match: the greatest wins.
+    pub(crate) fn priority(&self, tprim: usize) -> usize {
+        self.priorities[tprim]
+    }
+
+    /// How a match from i to end of the TPRIM ranks among the others starting at i, the greatest winning.
+    pub(crate) fn rank(&self, tprim: usize, i: usize, end: usize) -> (usize, usize) {
+        match self.longest_match {
+            true => (end - i, self.priority(tprim)),
+            false => (0, self.priority(tprim))
+        }
+    }
+
+    /// The priority of a string written in a group rule, which is tried before every TPRIM. In the groups the priorities
+    /// of the TPRIMs are shifted by one, above the repetitions which can be skipped, so the strings are two above them.
+    pub(crate) fn literal_priority(&self) -> usize {
+        self.priorities.iter().max().map_or(1, |max| max + 2)
+    }
+}
+
+/// Compile the forest of the identity, the repetitions it contains are pushed in repeats and its strings in literals.
+fn compile_forest(interp: &Interpreteur, atoms: &HashMap<&str, Atom>, repeats: &mut Vec<Repetition>, literals: &mut Vec<Literal>, identity: &Identity, forest: &Forest, in_group: bool) -> Result<Vec<Rule>, Diagnostic> {
+    let mut res = Vec::new();
+    for node in forest.iter() {
+        if let Node::Repeat(_, body, quantifier, can_end, sons) = node {
+            let body = compile_forest(interp, atoms, repeats, literals, identity, body, in_group)?;
+            let atom = Atom::Repeat(repeats.len());
+            repeats.push(Repetition{body, min: quantifier.min, max: quantifier.max});
+            let next = compile_forest(interp, atoms, repeats, literals, identity, sons, in_group)?;
+            res.push(Rule{atom, constraints: Vec::new(), can_end: *can_end, next});
+            continue;
+        }
+        let atom = match atoms.get(node.typechar()) {
+            _ if node.typechar().starts_with('"') => literal(interp, literals, identity, node.typechar())?,
+            Some(atom) => *atom,
+            None => return Err(interp.error(node.typechar(), format!("{} is used in the rule of {} but has never been declared.", node.typechar(), identity.name())))
+        };
+        match (atom, in_group) {
+            (Atom::Char(_), true) => return Err(interp.error(node.typechar(), format!("The group {} can't be built from the CHAR {}.", identity.name(), node.typechar()))),
+            (Atom::Group(_), false) => return Err(interp.error(node.typechar(), format!("The primitive token {} can't be built from the GROUP {}.", identity.name(), node.typechar()))),
+            _ => ()
+        }
+        let constraints = compile_constraints(interp, node.typechar(), node.constraints())?;
+        res.push(match node {
+            Node::Leaf(_, _) => Rule{atom, constraints, can_end: true, next: Vec::new()},
+            Node::Node(_, can_end, forest, _) => Rule{atom, constraints, can_end: *can_end, next: compile_forest(interp, atoms, repeats, literals, identity, forest, in_group)?},
+            Node::Repeat(..) => unreachable!()
+        });
+    }
+    Ok(res)
+}
+
+/// The trivia of the #TRIVIA section, the whitespaces by default.
+fn compile_trivia(interp: &Interpreteur, grammar: &Grammar) -> Result<Trivia, Diagnostic> {
+    let trivia = interp.trivia();
+    let string = |s: &str| unescape(s).map_err(|(i, message)| interp.error(&s[i..], format!("{message} in the #TRIVIA section.")));
+    let mut res = Trivia::default();
+    if let Some(skip) = &trivia.skip {
+        let mut sets = Vec::new();
+        for name in skip.iter() {
+            sets.push(grammar.char_set(name).ok_or_else(|| interp.error(name, format!("{name} is skipped in the #TRIVIA section but isn't a CHAR.")))?);
+        }
+        res.chars = CharSet::union(sets.into_iter());
+    }
+    for line in trivia.lines.iter() {
+        res.lines.push(string(line)?);
+    }
+    for (open, close, nested) in trivia.blocks.iter() {
+        res.blocks.push(Block{open: string(open)?, close: string(close)?, nested: *nested});
+    }
+    Ok(res)
+}
+
+/// The constraints of owner, with their strings unescaped.
+fn compile_constraints(interp: &Interpreteur, owner: &str, constraints: &[&str]) -> Result<Vec<Constraint>, Diagnostic> {
+    constraints.iter().map(|constraint| match constraint.starts_with('"') {
+        true => unescape(constraint).map(Constraint::Text)
+            .map_err(|(i, message)| interp.error(&constraint[i..], format!("{message} in the constraints of {owner}."))),
+        false => Ok(Constraint::Contains(constraint.to_string()))
+    }).collect()
+}
+
+/// The atom of a string written in a rule, the same strings sharing the same literal.
+fn literal(interp: &Interpreteur, literals: &mut Vec<Literal>, identity: &Identity, name: &str) -> Result<Atom, Diagnostic> {
+    let text = unescape(name).map_err(|(i, message)| interp.error(&name[i..], format!("{message} in the rule of {}.", identity.name())))?;
+    if let Some(i) = literals.iter().position(|literal| literal.text == text) {
+        return Ok(Atom::Literal(i))
+    }
+    literals.push(Literal{name: name.to_string(), text});
+    Ok(Atom::Literal(literals.len() - 1))
+}
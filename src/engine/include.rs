@@ -0,0 +1,202 @@
+pub use crate::charset::CharSet;
+pub use crate::tokenizer::include::ContentType;
+pub use std::collections::HashMap;
+
+pub static DEFAULT_TRIVIA: &[char; 4] = &[' ', '\t', '\n', '\r'];
+pub static WHITESPACE: &str = "Whitespace";     // The names of the trivia lexemes
+pub static COMMENT: &str = "Comment";
+pub static SPAWN_BOUND: usize = 64;           // The tokens a spawned tokenizer can match before they are received
+
+/// What is skipped between the lexemes: characters and comments.
+#[derive(Debug)]
+pub struct Trivia {
+    pub chars: CharSet,
+    pub lines: Vec<String>,     // The openers of the comments going to the end of the line
+    pub blocks: Vec<Block>,
+}
+
+impl Default for Trivia {
+    fn default() -> Trivia {
+        Trivia{chars: CharSet::new(DEFAULT_TRIVIA.iter().map(|c| (*c, *c)).collect(), false), lines: Vec::new(), blocks: Vec::new()}
+    }
+}
+
+impl Trivia {
+    /// The end of the piece of trivia starting at i, a run of skipped characters or a comment, and its name.
+    pub fn next(&self, text: &str, i: usize) -> Option<(usize, &'static str)> {
+        let rest = &text[i..];
+        let spaces = rest.find(|c| !self.chars.contains(c)).unwrap_or(rest.len());
+        if spaces > 0 {
+            Some((i + spaces, WHITESPACE))
+        } else if self.lines.iter().any(|line| rest.starts_with(line.as_str())) {
+            Some((i + rest.find('\n').unwrap_or(rest.len()), COMMENT))
+        } else {
+            let end = self.blocks.iter().filter(|block| rest.starts_with(block.open.as_str())).find_map(|block| block.end(text, i))?;
+            Some((end, COMMENT))
+        }
+    }
+
+    /// The block comment opened at i which is never closed, if any.
+    pub fn unclosed(&self, text: &str, i: usize) -> Option<&Block> {
+        self.blocks.iter().find(|block| text[i..].starts_with(block.open.as_str()) && block.end(text, i).is_none())
+    }
+}
+
+/// A comment between two strings, which can contain other ones if it nests.
+#[derive(Debug)]
+pub struct Block {
+    pub open: String,
+    pub close: String,
+    pub nested: bool,
+}
+
+impl Block {
+    /// The end of the comment opened at i, None if it's never closed.
+    pub fn end(&self, text: &str, mut i: usize) -> Option<usize> {
+        let mut depth = 0;
+        loop {
+            let rest = &text[i..];
+            if depth > 0 && rest.starts_with(self.close.as_str()) {
+                depth -= 1;
+                i += self.close.len();
+                if depth == 0 {
+                    return Some(i)
+                }
+            } else if (depth == 0 || self.nested) && rest.starts_with(self.open.as_str()) {
+                depth += 1;
+                i += self.open.len();
+            } else {
+                i += rest.chars().next()?.len_utf8();
+            }
+        }
+    }
+}
+
+/// What a node of a compiled rule has to match, as an index in the matching table of the grammar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Atom {
+    Char(usize),
+    Tprim(usize),
+    Group(usize),
+    Repeat(usize),
+    Literal(usize)
+}
+
+/// What a match has to be to be accepted by a rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Constraint {
+    Text(String),       // Its text
+    Contains(String),   // A group match containing a lexeme of this TPRIM
+}
+
+/// If one of the constraints accepts the match of text, whose lexemes are given.
+pub fn satisfied(constraints: &[Constraint], text: &str, lexemes: &[Lexeme]) -> bool {
+    constraints.is_empty() || constraints.iter().any(|constraint| match constraint {
+        Constraint::Text(t) => t == text,
+        Constraint::Contains(name) => lexemes.iter().any(|lexeme| lexeme.kind == LexemeKind::Tprim && lexeme.name == name)
+    })
+}
+
+/// The strings of the constraints, the only ones a TPRIM can have.
+pub fn texts(constraints: &[Constraint]) -> Vec<&str> {
+    constraints.iter().filter_map(|constraint| match constraint {
+        Constraint::Text(text) => Some(text.as_str()),
+        Constraint::Contains(_) => None
+    }).collect()
+}
+
+/// A node of a compiled forest: match the atom, then either stop if can_end or continue with one of the next rules.
+/// A rule without next rules is a leaf.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub atom: Atom,
+    pub constraints: Vec<Constraint>,
+    pub can_end: bool,
+    pub next: Vec<Rule>,
+}
+
+impl Rule {
+    pub fn is_leaf(&self) -> bool {
+        self.next.is_empty()
+    }
+
+    pub fn constraint_satisfied(&self, text: &str, lexemes: &[Lexeme]) -> bool {
+        satisfied(&self.constraints, text, lexemes)
+    }
+}
+
+/// A forest matched between min and max times in a row, max being None if unbounded.
+#[derive(Debug)]
+pub struct Repetition {
+    pub body: Vec<Rule>,
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Repetition {
+    pub fn can_repeat(&self, count: usize) -> bool {
+        self.max.is_none_or(|max| count < max)
+    }
+}
+
+/// A string written in a rule, which matches only itself.
+#[derive(Debug)]
+pub struct Literal {
+    pub name: String,   // The string as written in the rule, with its quotes
+    pub text: String,
+}
+
+#[derive(Debug)]
+pub struct CharClass {
+    pub name: String,
+    pub set: CharSet,
+}
+
+impl CharClass {
+    pub fn contains(&self, c: char) -> bool {
+        self.set.contains(c)
+    }
+}
+
+/// A TPRIM or a GROUP with its compiled forest.
+#[derive(Debug)]
+pub struct Production {
+    pub name: String,
+    pub forest: Vec<Rule>,
+    pub constraints: Vec<Constraint>,
+    pub negated: bool,      // The constraints are what a match can't be
+}
+
+impl Production {
+    pub fn constraint_satisfied(&self, text: &str, lexemes: &[Lexeme]) -> bool {
+        self.constraints.is_empty() || satisfied(&self.constraints, text, lexemes) != self.negated
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LexemeKind {
+    Tprim,
+    Literal,
+    Group,
+    Trivia      // Only pushed if the grammar keeps its trivia
+}
+
+/// A piece of the input recognized by the engine, the group lexemes come before the lexemes they contain.
+#[derive(Debug, Clone)]
+pub struct Lexeme<'g> {
+    pub name: &'g str,
+    pub kind: LexemeKind,
+    pub content: ContentType,
+    pub size: usize,        // For a group, the number of lexemes it contains, which follow it
+}
+
+impl<'g> Lexeme<'g> {
+    pub fn new(name: &'g str, kind: LexemeKind, content: ContentType) -> Lexeme<'g> {
+        Lexeme{name, kind, content, size: 0}
+    }
+
+    /// The part of the tokenized input covered by the lexeme.
+    pub fn text<'i>(&self, input: &'i str) -> &'i str {
+        &input[self.content.0..self.content.1]
+    }
+}
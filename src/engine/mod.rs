@@ -0,0 +1,5 @@
+pub mod include;
+pub mod grammar;
+pub mod tree;
+#[allow(clippy::module_inception)]
+pub mod engine;
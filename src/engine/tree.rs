@@ -0,0 +1,45 @@
+use super::include::*;
+
+/// A node of the concrete syntax tree: a lexeme, and the lexemes it contains if it's a group.
+#[derive(Debug, Clone)]
+pub struct Tree<'g> {
+    pub lexeme: Lexeme<'g>,
+    pub children: Vec<Tree<'g>>,
+}
+
+impl<'g> Tree<'g> {
+
+    /// Nest the lexemes returned by the engine, every group being followed by the lexemes it contains.
+    pub fn build(lexemes: &[Lexeme<'g>]) -> Vec<Tree<'g>> {
+        let mut res = Vec::new();
+        let mut i = 0;
+        while i < lexemes.len() {
+            let lexeme = &lexemes[i];
+            let children = Tree::build(&lexemes[i+1..i+1+lexeme.size]);
+            res.push(Tree{lexeme: lexeme.clone(), children});
+            i += lexeme.size + 1;
+        }
+        res
+    }
+
+    pub fn name(&self) -> &'g str {
+        self.lexeme.name
+    }
+
+    pub fn is_group(&self) -> bool {
+        self.lexeme.kind == LexemeKind::Group
+    }
+
+    /// The part of the tokenized input covered by the node.
+    pub fn text<'i>(&self, input: &'i str) -> &'i str {
+        self.lexeme.text(input)
+    }
+
+    /// The lexemes which aren't groups under the node, in the order of the input.
+    pub fn leaves(&self) -> Vec<&Lexeme<'g>> {
+        match self.is_group() {
+            true => self.children.iter().flat_map(|child| child.leaves()).collect(),
+            false => vec!(&self.lexeme)
+        }
+    }
+}
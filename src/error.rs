@@ -0,0 +1,59 @@
+use std::fmt;
+use crate::diagnostic::Diagnostic;
+
+/// Everything that can go wrong while loading a grammar or tokenizing a text with it.
+#[derive(Debug)]
+pub enum Error {
+    /// The file at this path couldn't be read.
+    Io(String, std::io::Error),
+    /// The .tl text isn't a valid grammar, every problem found in it.
+    Grammar(Vec<Diagnostic>),
+    /// The input doesn't match the grammar.
+    Input(Diagnostic),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "error: The path {path} isn't valid: {e}"),
+            Error::Grammar(diagnostics) => {
+                for diagnostic in diagnostics.iter() {
+                    writeln!(f, "{diagnostic}\n")?;
+                }
+                let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+                let warnings = diagnostics.len() - errors;
+                match errors {
+                    1 => write!(f, "error: could not interpret the grammar due to 1 previous error")?,
+                    n => write!(f, "error: could not interpret the grammar due to {n} previous errors")?
+                }
+                match warnings {
+                    0 => Ok(()),
+                    1 => write!(f, "; 1 warning emitted"),
+                    n => write!(f, "; {n} warnings emitted")
+                }
+            },
+            Error::Input(diagnostic) => write!(f, "{diagnostic}")
+        }
+    }
+}
+
+impl Error {
+
+    /// Set the file the diagnostic of the error comes from.
+    pub fn in_file(self, path: &str) -> Error {
+        match self {
+            Error::Grammar(diagnostics) => Error::Grammar(diagnostics.into_iter().map(|d| d.in_file(path)).collect()),
+            Error::Input(diagnostic) => Error::Input(diagnostic.in_file(path)),
+            e => e
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, e) => Some(e),
+            _ => None
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use super::include::*;
+use super::interpreteur::Interpreteur;
+use std::fmt::Write;
+
+/// Write the forests of the TPRIMs and of the GROUPs as a Graphviz graph. Every rule is a cluster starting from
+/// the name it defines, each node pointing to the nodes which can follow it and each repetition to its body.
+/// The nodes after which the rule can end have a double border, and a use of a TPRIM or a GROUP is a dashed link
+/// to its rule.
+pub fn dot(interp: &Interpreteur) -> String {
+    let mut writer = DotWriter{interp, out: String::new(), count: 0, rules: HashMap::new(), links: Vec::new()};
+    writer.write();
+    writer.out
+}
+
+struct DotWriter<'i, 'a> {
+    interp: &'i Interpreteur<'a>,
+    out: String,
+    count: usize,                       // The number of nodes written
+    rules: HashMap<&'i str, String>,    // The node of every rule, where it starts
+    links: Vec<(String, &'a str)>,      // The nodes using a rule, written last so they don't move the clusters
+}
+
+impl<'i, 'a> DotWriter<'i, 'a> {
+
+    fn write(&mut self) {
+        let _ = writeln!(self.out, "digraph grammar {{\n    rankdir=LR;\n    node [shape=box, fontname=\"monospace\"];");
+        let symbols = self.interp.symbols();
+        for (kind, name, class) in symbols.iter() {
+            if matches!(class, SymbolClass::Tprim | SymbolClass::Group) {
+                self.rules.insert(name, format!("rule{}", kind.id()));
+            }
+        }
+        for (kind, name, class) in symbols.iter() {
+            let identity = match class {
+                SymbolClass::Tprim => &self.interp.token_types()[name],
+                SymbolClass::Group => &self.interp.group_types()[name],
+                _ => continue
+            };
+            let mut label = format!("{} {name}", class.describe());
+            if !identity.constraints().is_empty() {
+                let op = if identity.is_negated() {"not in"} else {"in"};
+                label.push_str(&format!(" {op} [{}]", identity.constraints().join(", ")));
+            }
+            let rule = self.rules[name].clone();
+            let _ = writeln!(self.out, "\n    subgraph cluster_{} {{\n        label=\"{}\";", kind.id(), escape(&label));
+            let _ = writeln!(self.out, "        {rule} [label=\"{}\", shape=ellipse, style=bold];", escape(name));
+            self.write_forest(&rule, identity.forest(), "");
+            let _ = writeln!(self.out, "    }}");
+        }
+        let _ = writeln!(self.out);
+        for (node, name) in self.links.iter() {
+            let _ = writeln!(self.out, "    {node} -> {} [style=dashed, color=gray, constraint=false];", self.rules[name]);
+        }
+        let _ = writeln!(self.out, "}}");
+    }
+
+    /// Write the nodes of the forest, linked to parent by edges with the attributes.
+    fn write_forest(&mut self, parent: &str, forest: &[Node<'a>], attributes: &str) {
+        for node in forest.iter() {
+            let id = format!("n{}", self.count);
+            self.count += 1;
+            let (label, can_end) = match node {
+                Node::Leaf(root, constraints) => (with_constraints(root, constraints), true),
+                Node::Node(root, can_end, _, constraints) => (with_constraints(root, constraints), *can_end),
+                Node::Repeat(_, _, quantifier, can_end, sons) => (quantifier.to_string(), *can_end || sons.is_empty())
+            };
+            let end = if can_end {", peripheries=2, style=filled, fillcolor=palegreen"} else {""};
+            let _ = writeln!(self.out, "        {id} [label=\"{}\"{end}];", escape(&label));
+            let _ = writeln!(self.out, "        {parent} -> {id}{attributes};");
+            if let Some(SymbolClass::Tprim | SymbolClass::Group) = self.interp.symbols().class_of(node.typechar()) {
+                self.links.push((id.clone(), node.typechar()));
+            }
+            if let Node::Repeat(_, body, _, _, _) = node {
+                self.write_forest(&id, body, " [style=dotted, label=\"body\"]");
+            }
+            self.write_forest(&id, node.sons(), "");
+        }
+    }
+}
+
+/// The text written in a quoted string of the DOT language.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
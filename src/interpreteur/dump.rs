@@ -0,0 +1,56 @@
+use super::include::*;
+use super::interpreteur::Interpreteur;
+use std::fmt::Write;
+
+/// Write every CHAR class of the interpreted grammar with its rule as written, and every TPRIM and GROUP with its
+/// forest, in declaration order. A forest is an indented tree: the sons of a node are the nodes which can follow it.
+/// The nodes after which the rule can end are marked [END].
+pub fn dump(interp: &Interpreteur) -> String {
+    let mut out = String::new();
+    for (_, name, class) in interp.symbols().iter() {
+        match class {
+            SymbolClass::Char => {
+                let _ = match interp.symb_rule(name) {
+                    Some(rule) => writeln!(out, "CHAR {name} = {rule}"),
+                    None => writeln!(out, "CHAR {name}, without rule")
+                };
+            },
+            SymbolClass::Tprim => write_identity(&mut out, "TPRIM", &interp.token_types()[name]),
+            SymbolClass::Group => write_identity(&mut out, "GROUP", &interp.group_types()[name]),
+            SymbolClass::Literal | SymbolClass::Trivia => ()
+        }
+    }
+    out
+}
+
+fn write_identity(out: &mut String, class: &str, identity: &Identity) {
+    let _ = write!(out, "{class} {}", identity.name());
+    if !identity.constraints().is_empty() {
+        let op = if identity.is_negated() {"not in"} else {"in"};
+        let _ = write!(out, " {op} [{}]", identity.constraints().join(", "));
+    }
+    if identity.forest().is_empty() {
+        let _ = write!(out, ", without rule");
+    }
+    out.push('\n');
+    write_forest(out, identity.forest(), 1);
+}
+
+fn write_forest(out: &mut String, forest: &[Node], depth: usize) {
+    for node in forest.iter() {
+        let (text, can_end) = match node {
+            Node::Leaf(root, constraints) => (with_constraints(root, constraints), true),
+            Node::Node(root, can_end, _, constraints) => (with_constraints(root, constraints), *can_end),
+            Node::Repeat(_, body, quantifier, can_end, sons) => {
+                let repeated = match body.as_slice() {
+                    [Node::Leaf(root, constraints)] => format!("{}{quantifier}", with_constraints(root, constraints)),
+                    _ => format!("({}){quantifier}", forest_to_rule(body))
+                };
+                (repeated, *can_end || sons.is_empty())
+            }
+        };
+        let end = if can_end {"  [END]"} else {""};
+        let _ = writeln!(out, "{}{text}{end}", "    ".repeat(depth));
+        write_forest(out, node.sons(), depth + 1);
+    }
+}
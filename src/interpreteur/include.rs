@@ -1,17 +1,20 @@
-pub use crate::tokenizer::include::{Token, TokenType};
-pub type ConsumeResult = Result<(), String>;
+pub use crate::tokenizer::include::{Token, HEADER, IDENT, STRING};
+pub use crate::symbols::{SymbolTable, SymbolClass};
+pub use crate::diagnostic::Diagnostic;
+pub use crate::charset::CharSet;
+pub type ConsumeResult = Result<(), Diagnostic>;
 pub use std::collections::HashMap;
-pub use std::str::Chars;
-pub use std::iter::Peekable;
 
 pub type TypeChar<'a> = &'a str;
-pub type TypeGroup<'a> = &'a str;
 pub type Forest<'a> = Vec::<Node<'a>>;
+pub type Constraints<'a> = Vec::<&'a str>;     // Strings written with their quotes, or names of TPRIMs
 
 pub struct Identity<'a> {
     name: &'a str,
     forest: Forest<'a>,
-    constraints: Option<Vec<&'a str>>,
+    constraints: Constraints<'a>,
+    negated: bool,      // The constraints are what a match can't be
+    group: bool,
 }
 
 impl<'a> Identity<'a> {
@@ -20,7 +23,9 @@ impl<'a> Identity<'a> {
         Identity{
             name,
             forest: Forest::new(),
-            constraints: Some(Vec::new())
+            constraints: Constraints::new(),
+            negated: false,
+            group: false
         }
     }
 
@@ -28,31 +33,124 @@ impl<'a> Identity<'a> {
         Identity{
             name,
             forest: Forest::new(),
-            constraints: None
+            constraints: Constraints::new(),
+            negated: false,
+            group: true
         }
     }
 
-    pub fn set_constraints(&mut self, new_constraints: Vec::<&'a str>) -> Result<(), String>{
-        if self.constraints.is_some(){
-            self.constraints = Some(new_constraints);
-            Ok(())
-        } else {
-            return Err(format!("You tried to assign constraints to a group, it's not supported by the language yet."))
-        }
+    pub fn set_constraints(&mut self, new_constraints: Constraints<'a>, negated: bool) {
+        self.constraints = new_constraints;
+        self.negated = negated;
     }
     
     pub fn set_forest(&mut self, new_forest: Forest<'a>) -> Result<(), String> {
         self.forest = new_forest;
         Ok(())
     }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn forest(&self) -> &Forest<'a> {
+        &self.forest
+    }
+
+    /// What a match of the token has to be: one of the strings, or for a group containing one of the TPRIMs.
+    pub fn constraints(&self) -> &Constraints<'a> {
+        &self.constraints
+    }
+
+    /// If the token is declared with not in, a match satisfying one of the constraints being rejected.
+    pub fn is_negated(&self) -> bool {
+        self.negated
+    }
+
+    pub fn is_group(&self) -> bool {
+        self.group
+    }
     
 }
 
+/// What the #TRIVIA section says to skip between the lexemes, the strings being written with their quotes.
+#[derive(Debug, Default)]
+pub struct Trivia<'a> {
+    pub skip: Option<Vec<&'a str>>,         // The CHAR classes skipped, None for the default whitespaces
+    pub lines: Vec<&'a str>,                // The openers of the comments going to the end of the line
+    pub blocks: Vec<(&'a str, &'a str, bool)>,  // The openers and closers of the block comments, and if they nest
+}
+
+/// Write a forest back in the syntax of the rules.
+pub fn forest_to_rule(forest: &Forest) -> String {
+    let alternatives = forest.iter().map(|node| match node {
+        Node::Leaf(_, _) => node.to_rule(),
+        Node::Repeat(_, _, _, _, next) if next.is_empty() => node.to_rule(),
+        _ if forest.len() > 1 => format!("({})", node.to_rule()),
+        _ => node.to_rule()
+    });
+    alternatives.collect::<Vec<_>>().join(" || ")
+}
+
+/// The root written like in the rules, followed by its constraints between braces.
+pub fn with_constraints(root: &str, constraints: &[&str]) -> String {
+    match constraints.is_empty() {
+        true => root.to_string(),
+        false => format!("{root}{{{}}}", constraints.join(", "))
+    }
+}
+
+/// The forest followed by the sons wherever its rules can end, the rules still ending there if keep_ends.
+pub fn append_forest<'a>(forest: &Forest<'a>, sons: &Forest<'a>, keep_ends: bool) -> Forest<'a> {
+    if sons.is_empty() {
+        return forest.clone()
+    }
+    forest.iter().map(|node| match node {
+        Node::Leaf(tc, constraints) => Node::Node(tc, keep_ends, sons.clone(), constraints.clone()),
+        Node::Node(tc, can_end, next, constraints) => {
+            let mut next = append_forest(next, sons, keep_ends);
+            if *can_end {
+                merge_node_forests(&mut next, &mut sons.clone());
+            }
+            Node::Node(tc, *can_end && keep_ends, next, constraints.clone())
+        },
+        // A repetition without sons is a leaf
+        Node::Repeat(tc, body, quantifier, can_end, next) => {
+            let can_end = *can_end || next.is_empty();
+            let mut next = append_forest(next, sons, keep_ends);
+            if can_end {
+                merge_node_forests(&mut next, &mut sons.clone());
+            }
+            Node::Repeat(tc, body.clone(), *quantifier, can_end && keep_ends, next)
+        }
+    }).collect()
+}
+
+/// How many times the body of a repetition can be matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quantifier {
+    pub min: usize,
+    pub max: Option<usize>,     // None if unbounded
+}
+
+impl std::fmt::Display for Quantifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.min, self.max) {
+            (0, None) => write!(f, "*"),
+            (1, None) => write!(f, "+"),
+            (0, Some(1)) => write!(f, "?"),
+            (min, None) => write!(f, "{{{min},}}"),
+            (min, Some(max)) if min == max => write!(f, "{{{min}}}"),
+            (min, Some(max)) => write!(f, "{{{min},{max}}}")
+        }
+    }
+}
+
 fn merge_node_forests<'a>(f1: &mut Forest<'a>, f2: &mut Forest<'a>) {
     for node_f2 in f2.iter_mut() {
         let mut push_it = true;
         for node_f1 in f1.iter_mut() {
-            if node_f1.typechar() == node_f2.typechar() {
+            if node_f1.same_root(node_f2) {
                 push_it = false;
                 node_f1.merge(node_f2);
                 break
@@ -66,9 +164,11 @@ fn merge_node_forests<'a>(f1: &mut Forest<'a>, f2: &mut Forest<'a>) {
 
 #[derive(Debug)]
 #[derive(Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Node<'a> {
-    Node(TypeChar<'a>, bool, Forest<'a>),
-    Leaf(TypeChar<'a>)
+    Node(TypeChar<'a>, bool, Forest<'a>, Constraints<'a>),
+    Leaf(TypeChar<'a>, Constraints<'a>),
+    Repeat(TypeChar<'a>, Forest<'a>, Quantifier, bool, Forest<'a>)    // The text of the repetition, its body, then like a node
 }
 
 
@@ -76,23 +176,96 @@ impl<'a> Node<'a> {
 
     pub fn typechar(&self) -> TypeChar<'a> {
         match self {
-            Node::Node(tc, _, _) => tc,
-            Node::Leaf(tc) => tc
+            Node::Node(tc, _, _, _) => tc,
+            Node::Leaf(tc, _) => tc,
+            Node::Repeat(tc, _, _, _, _) => tc
+        }
+    }
+
+    pub fn constraints(&self) -> &[&'a str] {
+        match self {
+            Node::Node(_, _, _, constraints) => constraints,
+            Node::Leaf(_, constraints) => constraints,
+            Node::Repeat(_, _, _, _, _) => &[]
+        }
+    }
+
+    /// The nodes which can follow this one.
+    pub fn sons(&self) -> &[Node<'a>] {
+        match self {
+            Node::Node(_, _, sons, _) => sons,
+            Node::Leaf(_, _) => &[],
+            Node::Repeat(_, _, _, _, sons) => sons
+        }
+    }
+
+    /// Two nodes share the same root if they match the same type with the same constraints.
+    pub fn same_root(&self, other: &Node<'a>) -> bool {
+        matches!(self, Node::Repeat(..)) == matches!(other, Node::Repeat(..))
+            && self.typechar() == other.typechar() && self.constraints() == other.constraints()
+    }
+
+    /// Write the node and its sons in the syntax of the rules.
+    pub fn to_rule(&self) -> String {
+        let repeated;
+        let (root, is_end, sons, constraints) = match self {
+            Node::Node(tc, is_end, forest, constraints) => (*tc, *is_end, Some(forest), constraints.as_slice()),
+            Node::Leaf(tc, constraints) => (*tc, false, None, constraints.as_slice()),
+            Node::Repeat(_, body, quantifier, is_end, forest) => {
+                repeated = match body.as_slice() {
+                    [Node::Leaf(_, _)] => format!("{}{quantifier}", body[0].to_rule()),
+                    _ => format!("({}){quantifier}", forest_to_rule(body))
+                };
+                (repeated.as_str(), *is_end && !forest.is_empty(), Some(forest).filter(|f| !f.is_empty()), &[][..])
+            }
+        };
+        let mut args = constraints.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        if is_end {
+            args.push("END".to_string());
+        }
+        let mut res = match args.is_empty() {
+            true => root.to_string(),
+            false => format!("{root}{{{}}}", args.join(", "))
+        };
+        match sons {
+            Some(sons) if sons.len() == 1 => res.push_str(&format!(" && {}", sons[0].to_rule())),
+            Some(sons) => res.push_str(&format!(" && ({})", forest_to_rule(sons))),
+            None => ()
         }
+        res
     }
 
     pub fn merge(&mut self, node: &mut Node<'a>) {
         match self {
-            Node::Node(_, can_end, forest) => {
+            Node::Node(_, can_end, forest, _) => {
                 match node {
-                    Node::Leaf(_) => *can_end = true,
-                    Node::Node(_, _, new_forest) => merge_node_forests(forest, new_forest)
+                    Node::Node(_, is_end, new_forest, _) => {
+                        *can_end |= *is_end;
+                        merge_node_forests(forest, new_forest)
+                    },
+                    _ => *can_end = true
                 }
             },
-            Node::Leaf(_) => {
-                match node {
-                    Node::Node(root, _, forest) => *self = Node::Node(root, true, forest.to_vec()),
-                    Node::Leaf(_) => ()
+            Node::Leaf(_, _) => {
+                if let Node::Node(root, _, forest, constraints) = node {
+                    *self = Node::Node(root, true, forest.to_vec(), constraints.to_vec())
+                }
+            },
+            // A repetition without sons is a leaf
+            Node::Repeat(_, _, _, can_end, forest) => {
+                if let Node::Repeat(_, _, _, is_end, new_forest) = node {
+                    match (forest.is_empty(), new_forest.is_empty()) {
+                        (true, true) => (),
+                        (true, false) => {
+                            *can_end = true;
+                            *forest = new_forest.to_vec();
+                        },
+                        (false, true) => *can_end = true,
+                        (false, false) => {
+                            *can_end |= *is_end;
+                            merge_node_forests(forest, new_forest)
+                        }
+                    }
                 }
             }
         }
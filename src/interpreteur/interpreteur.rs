@@ -1,229 +1,535 @@
 use super::include::*;
 
-type Consumer<'a> = fn(&mut Interpreteur<'a>, &'a str, &'a str, &'a str, TokenType) -> ConsumeResult;
+type Consumer<'a> = fn(&mut Interpreteur<'a>, Statement<'a>) -> ConsumeResult;
 
-pub struct Interpreteur<'a> {
+/// A line of a section, as the meta-grammar read it.
+struct Statement<'a> {
+    name: &'a str,              // What the line defines, before its operator
+    op: &'a str,                // =, in or not in
+    value: &'a str,             // What follows the operator
+    items: Vec<&'a str>,        // The names and the strings of the value, for a list
+}
+
+pub(crate) struct Interpreteur<'a> {
     text: &'a str,
-    symb_types: HashMap<&'a str, &'a str>,
+    symb_types: HashMap<&'a str, Option<CharSet>>,
+    symb_rules: HashMap<&'a str, &'a str>,       // The rules of the CHAR classes, as they are written
+    macros: HashMap<&'a str, CharSet>,           // The macros defined in the #MACROS section
+    priorities: HashMap<&'a str, usize>,         // The priorities of the #PRIORITIES section
+    longest_match: bool,                         // Set by MATCH = longest in the #OPTIONS section
+    keep_trivia: bool,                           // Set by TRIVIA = keep in the #OPTIONS section
+    trivia: Trivia<'a>,
     token_types: HashMap<&'a str, Identity<'a>>,
     group_types: HashMap<&'a str, Identity<'a>>,
-    current_section: &'a str,
+    declared: Vec<&'a str>,                      // Every declared name, in the order of the #DECLARE section
+    symbols: SymbolTable,                        // The token kinds of the declared names
+    kinds: &'static SymbolTable,                 // The token kinds of the lines read, shared with the tokenizer
     sections: HashMap<&'a str, Consumer<'a>>,
 }
 
 impl<'a> Interpreteur<'a> {
 
-    pub fn new(text: &str) -> Interpreteur<'_> {
+    pub fn new(text: &'a str, kinds: &'static SymbolTable) -> Interpreteur<'a> {
         Interpreteur {
             text,
             symb_types: HashMap::new(),
+            symb_rules: HashMap::new(),
+            macros: HashMap::new(),
+            priorities: HashMap::new(),
+            longest_match: false,
+            keep_trivia: false,
+            trivia: Trivia::default(),
             token_types: HashMap::new(),
             group_types: HashMap::new(),
-            current_section: "",
+            declared: Vec::new(),
+            symbols: SymbolTable::new(),
+            kinds,
             sections: Interpreteur::build_section_map(),
         }
     }
 
-    pub fn new_token(&mut self, token: Token) -> ConsumeResult {
-        let (i, j) = token.content;
-        let line = &self.text[i..j].trim();
-        if line.starts_with("#") {
-            self.current_section = &line[1..];
-            Ok(())
-        } else {
-            let token_meth = *self.sections.get(self.current_section).expect(&format!("The section {} doesn't exist", self.current_section));
-            let (left, op, right) = self.split_line(line);
-            token_meth(self, left.trim(), op.trim(), right.trim(), token.token_type)
+    /// Interpret the tokens of a line, the first one spanning the line and being of the kind of its section.
+    /// The headers only matter to the tokenizer, which reads the lines of every section with their own syntax.
+    pub fn new_line(&mut self, tokens: &[Token]) -> ConsumeResult {
+        let section = self.kinds.name(tokens[0].kind);
+        if section == HEADER {
+            return Ok(())
         }
+        let consumer = self.sections[section];
+        consumer(self, self.statement(tokens))
     }
 
-    fn split_line(&self, line: &'a str) -> (&'a str, &'a str, &'a str) {
-        let mut space = false;
-        let mut prev = 'a';  // Non signigicant character
-        let mut i = 0;
-        for c in line.chars() {
-            match c {
-                ' ' => space = true,
-                'n' => if space && prev == 'i' {
-                    return (&line[0..i-1], &line[i-1..i+1], &line[i+1..])
-                },
-                '=' => return (&line[0..i], &line[i..i+1], &line[i+1..]),
-                _ => ()
-            }
-            i += 1;
-            prev = c;
-        }
-        panic!("Failed to tokenize a line");
+    /// The parts of a line from its tokens: the first Ident, the operator following it and the value following
+    /// the operator, with the names and the strings the value contains.
+    fn statement(&self, tokens: &[Token]) -> Statement<'a> {
+        let kind = |token: &Token| self.kinds.name(token.kind);
+        let text = |token: &Token| &self.text[token.content.0..token.content.1];
+        let first = tokens.iter().position(|token| kind(token) == IDENT).expect("The lines of the sections start with an Ident");
+        let (name, op) = (&tokens[first], &tokens[first + 1]);
+        let value = tokens[first + 2..].iter().find(|token| token.content.0 >= op.content.1).expect("An operator is followed by a value");
+        let items = tokens[first + 2..].iter()
+            .filter(|token| token.content.0 >= value.content.0 && token.content.1 <= value.content.1 && [IDENT, STRING].contains(&kind(token)))
+            .map(text)
+            .collect();
+        Statement{name: text(name), op: text(op), value: text(value), items}
     }
-    
 
-    fn define_token(&mut self,  left: &'a str, _op: &'a str, right: &'a str, token_type: TokenType) -> ConsumeResult {
+    /// A diagnostic pointing at a part of the interpreted text.
+    pub fn error(&self, at: &str, message: String) -> Diagnostic {
+        Diagnostic::new(self.text, self.offset(at), message)
+    }
+
+    /// The position in the interpreted text of one of its slices.
+    pub fn offset(&self, slice: &str) -> usize {
+        (slice.as_ptr() as usize).saturating_sub(self.text.as_ptr() as usize).min(self.text.len())
+    }
+
+    /// The CHAR classes, associated to their members, None if they have no rule.
+    pub fn symb_types(&self) -> &HashMap<&'a str, Option<CharSet>> {
+        &self.symb_types
+    }
+
+    /// The rule of a CHAR class as written in the #CHAR_RULES section, macros and ranges unexpanded.
+    pub fn symb_rule(&self, name: &str) -> Option<&'a str> {
+        self.symb_rules.get(name).copied()
+    }
+
+    pub fn token_types(&self) -> &HashMap<&'a str, Identity<'a>> {
+        &self.token_types
+    }
+
+    pub fn group_types(&self) -> &HashMap<&'a str, Identity<'a>> {
+        &self.group_types
+    }
+
+    /// The priorities given to the TPRIMs by the #PRIORITIES section.
+    pub fn priorities(&self) -> &HashMap<&'a str, usize> {
+        &self.priorities
+    }
+
+    /// If the longest match wins when several TPRIMs match, their priorities only breaking the ties.
+    pub fn longest_match(&self) -> bool {
+        self.longest_match
+    }
+
+    /// If the trivia are pushed as lexemes instead of being skipped.
+    pub fn keep_trivia(&self) -> bool {
+        self.keep_trivia
+    }
+
+    pub fn trivia(&self) -> &Trivia<'a> {
+        &self.trivia
+    }
+
+    /// The token kinds of the #DECLARE section, a name declared twice having the kind of its first declaration.
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// Every name of the #DECLARE section, in declaration order.
+    pub fn declared(&self) -> &Vec<&'a str> {
+        &self.declared
+    }
+
+    fn define_token(&mut self, line: Statement<'a>) -> ConsumeResult {
+        let left = line.name;
         match left {
             "CHARS" => {
-                for name in right.split(",") {
-                    self.symb_types.insert(name.trim(), "");
+                for name in line.items {
+                    self.symb_types.insert(name, None);
+                    self.declared.push(name);
+                    self.symbols.intern(name, SymbolClass::Char);
                 }
             }
             "TPRIMS" => {
-                for name in right.split(",") {
-                    let name = name.trim();
+                for name in line.items {
                     self.token_types.insert(name, Identity::token(name));
+                    self.declared.push(name);
+                    self.symbols.intern(name, SymbolClass::Tprim);
                 }
             }
             "GROUPS" => {
-                for name in right.split(",") {
-                    let name = name.trim();
+                for name in line.items {
                     self.group_types.insert(name, Identity::group(name));
+                    self.declared.push(name);
+                    self.symbols.intern(name, SymbolClass::Group);
                 }
             }
-            _ => return Err(format!("You can't define '{left}'"))
+            _ => return Err(self.error(left, format!("You can't define '{left}'")))
         };
         Ok(())
     }
 
-    fn tprim_rules_token(&mut self,  left: &'a str, op: &'a str, right: &'a str, token_type: TokenType) -> ConsumeResult {
+    fn tprim_rules_token(&mut self, line: Statement<'a>) -> ConsumeResult {
+        let (left, op) = (line.name, line.op);
         if !self.token_types.contains_key(left) {
-            return Err(format!("The primitve token {left} doesn't exists."))
+            return Err(self.error(left, format!("The primitve token {left} doesn't exists.")))
         }
         match op {
             "=" =>{
-                let forest = self.ptoken_building_tree(left, right)?;
-                self.token_types.get_mut(left).unwrap().set_forest(forest)?
+                let forest = self.ptoken_building_tree(left, line.value)?;
+                self.token_types.get_mut(left).unwrap().set_forest(forest).map_err(|e| self.error(left, e))?
             },
-            "in" => {
-                let mut constraints = Vec::new();
-                for constraint in right[1..right.len()-1].split(",") {
-                    let constraint = constraint.trim();
-                    constraints.push(&constraint[1..constraint.len()-1]);
-                }
-                self.token_types.get_mut(left).unwrap().set_constraints(constraints)?;
-            },
-            _ => return Err(format!("This operator isn't authorized here: {op}"))
-         };
+            _ => self.token_types.get_mut(left).unwrap().set_constraints(line.items, op != "in")
+        };
         Ok(())
     }
 
-    fn group_rules_token(&mut self,  left: &'a str, op: &'a str, right: &'a str, token_type: TokenType) -> ConsumeResult {
+    fn group_rules_token(&mut self, line: Statement<'a>) -> ConsumeResult {
+        let (left, op) = (line.name, line.op);
         if !self.group_types.contains_key(left) {
-            return Err(format!("The group token {left} doesn't exists."))
+            return Err(self.error(left, format!("The group token {left} doesn't exists.")))
         }
-        let forest = self.ptoken_building_tree(left, right)?;
-        self.group_types.get_mut(left).unwrap().set_forest(forest)?;
+        match op {
+            "=" => {
+                let forest = self.ptoken_building_tree(left, line.value)?;
+                self.group_types.get_mut(left).unwrap().set_forest(forest).map_err(|e| self.error(left, e))?
+            },
+            _ => self.group_types.get_mut(left).unwrap().set_constraints(line.items, op != "in")
+        };
         Ok(())
     }
     
-    fn symb_rules_token(&mut self,  left: &'a str, _op: &'a str, right: &'a str, token_type: TokenType)  -> ConsumeResult {
+    fn symb_rules_token(&mut self, line: Statement<'a>) -> ConsumeResult {
+        let (left, right) = (line.name, line.value);
         if self.symb_types.contains_key(left) {
-            self.symb_types.insert(left, &right[1..right.len()-1]);
+            let set = self.parse_charset(left, right)?;
+            self.symb_types.insert(left, Some(set));
+            self.symb_rules.insert(left, right);
             Ok(())
         } else {
-            Err(format!("{left} is an undefined symbol type."))
+            Err(self.error(left, format!("{left} is an undefined symbol type.")))
+        }
+    }
+
+    fn macro_rules_token(&mut self, line: Statement<'a>) -> ConsumeResult {
+        let (left, right) = (line.name, line.value);
+        if CharSet::predefined(left).is_some() {
+            return Err(self.error(left, format!("The macro @{left} is predefined, it can't be redefined.")))
+        }
+        if self.macros.contains_key(left) {
+            return Err(self.error(left, format!("The macro @{left} is defined twice.")))
+        }
+        let set = self.parse_charset(left, right)?;
+        self.macros.insert(left, set);
+        Ok(())
+    }
+
+    fn option_rules_token(&mut self, line: Statement<'a>) -> ConsumeResult {
+        let (left, right) = (line.name, line.value);
+        match left {
+            "MATCH" => self.longest_match = match right {
+                "longest" => true,
+                "priority" => false,
+                _ => return Err(self.error(right, format!("The TPRIMs are matched by longest or by priority, not by {right}.")))
+            },
+            "TRIVIA" => self.keep_trivia = match right {
+                "keep" => true,
+                "skip" => false,
+                _ => return Err(self.error(right, format!("The trivia are kept or skipped, {right} isn't possible.")))
+            },
+            _ => return Err(self.error(left, format!("The option {left} doesn't exist.")))
+        }
+        Ok(())
+    }
+
+    fn trivia_rules_token(&mut self, line: Statement<'a>) -> ConsumeResult {
+        let (left, op, right, list) = (line.name, line.op, line.value, line.items);
+        if op != "in" {
+            return Err(self.error(op, format!("The trivia {left} are listed with in [...].")))
+        }
+        if let Some(name) = list.iter().find(|item| item.starts_with('"') == (left == "SKIP")) {
+            let expected = if left == "SKIP" {"the names of CHAR classes"} else {"strings"};
+            return Err(self.error(name, format!("The trivia {left} are {expected}, {name} isn't one.")))
+        }
+        match (left, list.as_slice()) {
+            ("SKIP", _) => self.trivia.skip.get_or_insert_with(Vec::new).extend(list),
+            ("LINE", _) => self.trivia.lines.extend(list),
+            ("BLOCK" | "NESTED", [open, close]) => self.trivia.blocks.push((open, close, left == "NESTED")),
+            ("BLOCK" | "NESTED", _) => return Err(self.error(right, format!("{left} is given the strings opening and closing a comment, like [\"/*\", \"*/\"]."))),
+            _ => return Err(self.error(left, format!("{left} isn't a kind of trivia, they are SKIP, LINE, BLOCK or NESTED.")))
+        }
+        Ok(())
+    }
+
+    fn priority_rules_token(&mut self, line: Statement<'a>) -> ConsumeResult {
+        let (left, right) = (line.name, line.value);
+        if !self.token_types.contains_key(left) {
+            return Err(self.error(left, format!("Only the TPRIMs have a priority, {left} isn't one.")))
+        }
+        if self.priorities.contains_key(left) {
+            return Err(self.error(left, format!("The priority of {left} is given twice.")))
         }
+        let priority = right.parse::<usize>().map_err(|_| self.error(right, format!("The priority of {left} has to be a positive integer.")))?;
+        self.priorities.insert(left, priority);
+        Ok(())
+    }
+
+    fn parse_charset(&self, name: &'a str, right: &'a str) -> Result<CharSet, Diagnostic> {
+        CharSet::parse(right, &self.macros).map_err(|(i, message)| self.error(&right[i..], format!("{message} in the rule of {name}.")))
     }
 
     fn build_section_map() -> HashMap<&'a str, Consumer<'a>> {
         let mut res = HashMap::<&'a str, Consumer>::new();
         res.insert("DECLARE", Interpreteur::define_token);
+        res.insert("OPTIONS", Interpreteur::option_rules_token);
+        res.insert("MACROS", Interpreteur::macro_rules_token);
+        res.insert("PRIORITIES", Interpreteur::priority_rules_token);
+        res.insert("TRIVIA", Interpreteur::trivia_rules_token);
         res.insert("CHAR_RULES",  Interpreteur::symb_rules_token);
         res.insert("TPRIM_RULES", Interpreteur::tprim_rules_token);
         res.insert("GROUP_RULES", Interpreteur::group_rules_token);
         res
     }
 
-    fn extract_root(&self, mut root: &'a str) -> (&'a str, bool, Vec::<&'a str>) {
+    fn extract_root(&self, name: &'a str, root: &'a str) -> Result<(&'a str, bool, Constraints<'a>), Diagnostic> {
         let mut is_end = false;
-        let mut constraints = Vec::<&'a str>::new();
-        let mut split = root.split("{");
-        root = split.next().unwrap().trim();
-        if let Some(args) = split.next() {
-            let args = &args[0..args.len()-1];
-            let mut j = 0;
-            let mut comma = false;
-            for (i, c) in args.chars().enumerate() {
-                if c == ',' && !comma {
-                    (is_end, constraints) = self.match_node_arg(&args[j..i], constraints, is_end);
-                    j = i+1;
-                } else if c == '\"' {
-                    comma = !comma;
-                }
+        let mut constraints = Constraints::new();
+        let root = root.trim();
+        let (root, args) = match trailing_braces(root) {
+            Some(i) if !is_bounds(&root[i+1..root.len()-1]) => (root[0..i].trim(), &root[i+1..root.len()-1]),
+            _ => (root, "")
+        };
+        if root.is_empty() {
+            return Err(self.error(root, format!("An operand is missing in the rule of {name}")))
+        }
+        if !args.trim().is_empty() {
+            for arg in split_outside_quotes(args, ',') {
+                self.match_node_arg(name, arg, &mut constraints, &mut is_end)?;
             }
-            (is_end, constraints) = self.match_node_arg(&args[j..], constraints, is_end)
         }
-        (root, is_end, constraints)
+        Ok((root, is_end, constraints))
     }
 
-    fn match_node_arg(&self, mut arg: &'a str,
-                      mut constraints: Vec<&'a str>,
-                      mut is_end: bool
-    ) -> (bool, Vec<&'a str>) {
+    fn match_node_arg(&self, name: &'a str, mut arg: &'a str,
+                      constraints: &mut Constraints<'a>,
+                      is_end: &mut bool
+    ) -> ConsumeResult {
         arg = arg.trim();
-        match arg as &str {
-            "END" => is_end = true,
-            _ => constraints.push(&arg[1..arg.len()-1])
+        match arg {
+            "END" => *is_end = true,
+            _ if arg.len() >= 2 && arg.starts_with('"') && arg.ends_with('"') => constraints.push(arg),
+            _ if !arg.is_empty() && arg.chars().all(|c| c.is_alphanumeric() || c == '_') => constraints.push(arg),
+            _ => return Err(self.error(arg, format!("Invalid argument '{arg}' in the rule of {name}")))
         }
-        (is_end, constraints)
+        Ok(())
     }
     
-    fn ptoken_building_tree(&self, name: &'a str, mut expr: &'a str) -> Result<Forest<'a>, String> {
-        expr = expr.trim();
-        let mut iter = expr.chars().peekable();
+    fn ptoken_building_tree(&self, name: &'a str, mut expr: &'a str) -> Result<Forest<'a>, Diagnostic> {
+        expr = strip_parenthesis(expr.trim());
         let mut forest = Forest::new();
-        while iter.peek().is_some() {
+        while !expr.is_empty() {
             let sub_expr: &str;
-            (sub_expr, expr) = self.get_next_expr(expr, &mut iter, '|');
-            let (root, mut rest) = self.get_next_expr(sub_expr, &mut sub_expr.chars().peekable(), '&');
-            rest = rest.trim();
-            let (root, is_end, node_constraints) = self.extract_root(root);
-            let mut new_node = if rest.is_empty() {
-                Node::Leaf(root)
+            (sub_expr, expr) = self.get_next_expr(expr, '|');
+            let (root, rest) = self.get_next_expr(strip_parenthesis(sub_expr), '&');
+            let sons = if rest.is_empty() {
+                Forest::new()
             } else {
-                Node::Node(root, is_end, self.ptoken_building_tree(name, rest)?)
+                self.ptoken_building_tree(name, rest)?
             };
-            let mut push_it = true;
-            for node in forest.iter_mut() {
-                if node.typechar() == root {
-                    node.merge(&mut new_node);
-                    push_it = false;
-                    break;
+            for mut new_node in self.build_node(name, root, sons)? {
+                let mut push_it = true;
+                for node in forest.iter_mut() {
+                    if node.same_root(&new_node) {
+                        node.merge(&mut new_node);
+                        push_it = false;
+                        break;
+                    }
+                }
+                if push_it {
+                    forest.push(new_node)
                 }
             }
-            if push_it {
-                forest.push(new_node)
-            } 
         }
         Ok(forest)
     }
 
-    fn get_next_expr(&self, mut expr: &'a str, iter: &mut Peekable<Chars<'a>>, stop_char: char) -> (&'a str, &'a str) {
-        let mut res = 0;
+    /// The nodes matching root, a name, a repetition or a parenthesized expression, followed by the sons.
+    fn build_node(&self, name: &'a str, root: &'a str, sons: Forest<'a>) -> Result<Forest<'a>, Diagnostic> {
+        let (root, is_end, constraints) = self.extract_root(name, root)?;
+        if root.starts_with('"') && !constraints.is_empty() {
+            return Err(self.error(root, format!("The string {root} can't be constrained in the rule of {name}, it only matches itself.")))
+        }
+        let (body, quantifier) = match self.extract_quantifier(name, root)? {
+            Some(repetition) => repetition,
+            None if root.starts_with('"') && closing_quote(root) != Some(root.len() - 1) => {
+                return Err(self.error(root, format!("Several strings follow each other in the rule of {name}, only the rule of a CHAR can be written this way.")))
+            },
+            None if strip_parenthesis(root) != root => {
+                if !constraints.is_empty() {
+                    return Err(self.error(root, format!("The expression {root} can't be constrained in the rule of {name}, the constraints go on what it contains.")))
+                }
+                // Every alternative of the expression is followed by the sons
+                return Ok(append_forest(&self.ptoken_building_tree(name, root)?, &sons, is_end))
+            },
+            None if sons.is_empty() => return Ok(vec!(Node::Leaf(root, constraints))),
+            None => return Ok(vec!(Node::Node(root, is_end, sons, constraints)))
+        };
+        if !constraints.is_empty() {
+            return Err(self.error(root, format!("The repetition {root} can't be constrained in the rule of {name}, the constraints go on what is repeated.")))
+        }
+        let body = if strip_parenthesis(body) != body {
+            self.ptoken_building_tree(name, body)?
+        } else {
+            self.build_node(name, body, Forest::new())?
+        };
+        Ok(vec!(Node::Repeat(root, body, quantifier, is_end, sons)))
+    }
+
+    /// Split a repetition like digit+, (a && b)* or Ident{2,3} in the repeated expression and its quantifier.
+    fn extract_quantifier(&self, name: &'a str, root: &'a str) -> Result<Option<(&'a str, Quantifier)>, Diagnostic> {
+        let (body, quantifier) = match root.chars().last() {
+            Some('*') => (&root[..root.len()-1], Quantifier{min: 0, max: None}),
+            Some('+') => (&root[..root.len()-1], Quantifier{min: 1, max: None}),
+            Some('?') => (&root[..root.len()-1], Quantifier{min: 0, max: Some(1)}),
+            Some('}') => {
+                let i = match trailing_braces(root) {
+                    Some(i) => i,
+                    None => return Ok(None)
+                };
+                let bounds = &root[i+1..root.len()-1];
+                let parse = |bound: &str| match bound.trim() {
+                    "" => Ok(None),
+                    bound => bound.parse::<usize>().map(Some).map_err(|_| self.error(bounds, format!("Invalid bound {bound} in the rule of {name}")))
+                };
+                let (min, max) = match bounds.split_once(',') {
+                    Some((min, max)) => (parse(min)?.unwrap_or(0), parse(max)?),
+                    None => match parse(bounds)? {
+                        Some(n) => (n, Some(n)),
+                        None => return Ok(None)
+                    }
+                };
+                if max.is_some_and(|max| max == 0 || max < min) {
+                    return Err(self.error(bounds, format!("The bounds {{{bounds}}} of the repetition can't be matched in the rule of {name}")))
+                }
+                (&root[..i], Quantifier{min, max})
+            },
+            _ => return Ok(None)
+        };
+        let body = body.trim();
+        if body.is_empty() {
+            return Err(self.error(root, format!("Nothing is repeated in the rule of {name}")))
+        }
+        Ok(Some((body, quantifier)))
+    }
+
+    /// Split the expression on the first doubled stop_char ("||" or "&&") which is neither
+    /// between parenthesis nor in a string, and return the two trimmed sides.
+    fn get_next_expr(&self, expr: &'a str, stop_char: char) -> (&'a str, &'a str) {
         let mut comma = false;
+        let mut escaped = false;
         let mut par_count = 0;
-        let first = *iter.peek().expect("expr empty");
-        let mut c = first;
-        while let Some(new_char) = iter.next() {
-            c = new_char;
+        let mut chars = expr.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
             match c {
-                '(' => par_count += 1,
-                ')' => par_count -= 1,
+                _ if escaped => escaped = false,
+                '\\' if comma => escaped = true,
                 '\"' => comma = !comma,
+                '(' if !comma => par_count += 1,
+                ')' if !comma => par_count -= 1,
                 _ => {
-                    if c == stop_char && !comma && par_count == 0 {
-                        let _ = iter.next();
-                        return (&expr[0..(res-1)].trim(), &expr[(res+2)..expr.len()]);
+                    if c == stop_char && !comma && par_count == 0 && chars.peek().map(|(_, c)| *c) == Some(stop_char) {
+                        return (expr[0..i].trim(), expr[i+2..].trim());
                     }
                 }
             }
-            if par_count == -1 {
-                par_count = 0;
+        }
+        (expr.trim(), "")
+    }  
+}
+
+/// Split s on every sep which isn't in a string.
+fn split_outside_quotes(s: &str, sep: char) -> Vec<&str> {
+    let mut res = Vec::new();
+    let mut comma = false;
+    let mut escaped = false;
+    let mut j = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if comma => escaped = true,
+            '\"' => comma = !comma,
+            _ if c == sep && !comma => {
+                res.push(&s[j..i]);
+                j = i + 1;
             }
-            res += 1;
+            _ => ()
         }
-        if first == '(' && c == ')' {
-            expr = &expr[1..(res-1)];
-            *iter = expr.chars().peekable();
-            self.get_next_expr(expr, iter, stop_char)
-        } else {
-            (&expr[0..res].trim(), &expr[res..expr.len()])
+    }
+    res.push(&s[j..]);
+    res
+}
+
+/// The index of the brace opening the braces which end s, if it ends with braces.
+fn trailing_braces(s: &str) -> Option<usize> {
+    let mut comma = false;
+    let mut escaped = false;
+    let mut depth = 0;
+    let mut last = None;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if comma => escaped = true,
+            '\"' => comma = !comma,
+            '{' if !comma => {
+                if depth == 0 {
+                    last = Some(i);
+                }
+                depth += 1;
+            },
+            '}' if !comma => depth -= 1,
+            _ => ()
         }
-    }  
+    }
+    last.filter(|_| s.ends_with('}') && depth == 0)
+}
+
+/// If the content of braces is the bounds of a repetition, like 2,5 or 3.
+fn is_bounds(s: &str) -> bool {
+    !s.trim().is_empty() && s.chars().all(|c| c.is_ascii_digit() || c == ',' || c == ' ')
+}
+
+/// Remove the parenthesis surrounding the whole expression, if any.
+fn strip_parenthesis(mut expr: &str) -> &str {
+    while expr.starts_with('(') && closing_parenthesis(expr) == Some(expr.len() - 1) {
+        expr = expr[1..expr.len()-1].trim();
+    }
+    expr
+}
+
+/// The index of the quote closing the string starting expr.
+fn closing_quote(expr: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in expr.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '\"' => return Some(i),
+            _ => ()
+        }
+    }
+    None
+}
+
+/// The index of the parenthesis closing the one starting expr.
+fn closing_parenthesis(expr: &str) -> Option<usize> {
+    let mut comma = false;
+    let mut escaped = false;
+    let mut par_count = 0;
+    for (i, c) in expr.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if comma => escaped = true,
+            '\"' => comma = !comma,
+            '(' if !comma => par_count += 1,
+            ')' if !comma => {
+                par_count -= 1;
+                if par_count == 0 {
+                    return Some(i)
+                }
+            }
+            _ => ()
+        }
+    }
+    None
 }
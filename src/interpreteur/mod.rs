@@ -1,4 +1,8 @@
 pub mod include;
-pub mod stack;
+pub mod validation;
+pub mod recursion;
+pub mod dump;
+pub mod dot;
+#[allow(clippy::module_inception)]
 pub mod interpreteur;
 
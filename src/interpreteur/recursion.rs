@@ -0,0 +1,114 @@
+use super::include::*;
+use super::interpreteur::Interpreteur;
+use std::collections::HashSet;
+
+/// Find the groups which can start with themselves, directly or through other groups:
+/// a recursive descent would never stop on them. Every cycle is reported once, on its first declared group.
+pub fn left_recursions(interp: &Interpreteur) -> Vec<Diagnostic> {
+    let mut res = Vec::new();
+    let mut reported = HashSet::new();
+    for name in interp.declared().iter() {
+        let identity = match interp.group_types().get(name) {
+            Some(identity) if !reported.contains(name) => identity,
+            _ => continue
+        };
+        let cycle = match find_cycle(interp, name) {
+            Some(cycle) => cycle,
+            None => continue
+        };
+        reported.extend(cycle.iter().copied());
+        // The rule is pointed where it starts with the next group of the cycle
+        let at = identity.forest().iter().find(|node| first_names(std::slice::from_ref(node)).contains(&cycle[1])).unwrap().typechar();
+        let mut diagnostic = interp.error(at, format!("The group {name} is left recursive: {}.", cycle.join(" -> ")));
+        if cycle.len() == 2 {
+            if let Some(help) = rewrite(interp, identity) {
+                diagnostic = diagnostic.with_help(help);
+            }
+        }
+        res.push(diagnostic);
+    }
+    res
+}
+
+/// The names the rules of the forest can start with, a repetition which can be skipped letting its sons start them.
+fn first_names<'a>(forest: &[Node<'a>]) -> Vec<&'a str> {
+    let mut res = Vec::new();
+    for node in forest.iter() {
+        match node {
+            Node::Repeat(_, body, quantifier, _, sons) => {
+                res.extend(first_names(body));
+                if quantifier.min == 0 {
+                    res.extend(first_names(sons));
+                }
+            },
+            _ => res.push(node.typechar())
+        }
+    }
+    res
+}
+
+/// The groups a rule of the group can start with.
+fn first_groups<'a>(interp: &Interpreteur<'a>, name: &str) -> Vec<&'a str> {
+    let mut res = Vec::new();
+    for first in first_names(interp.group_types()[name].forest()) {
+        if interp.group_types().contains_key(first) && !res.contains(&first) {
+            res.push(first);
+        }
+    }
+    res
+}
+
+/// A path of groups, each starting the rules of the previous one, going from the group back to itself.
+fn find_cycle<'a>(interp: &Interpreteur<'a>, start: &'a str) -> Option<Vec<&'a str>> {
+    let mut path = vec!(start);
+    let mut stack = vec!(first_groups(interp, start));
+    let mut visited = HashSet::new();
+    while let Some(sons) = stack.last_mut() {
+        let son = match sons.pop() {
+            Some(son) => son,
+            None => {
+                stack.pop();
+                path.pop();
+                continue;
+            }
+        };
+        if son == start {
+            path.push(son);
+            return Some(path)
+        }
+        if visited.insert(son) {
+            path.push(son);
+            stack.push(first_groups(interp, son));
+        }
+    }
+    None
+}
+
+/// Rewrite the rules of a directly left recursive group as right recursion, with a new group matching the repeated tails:
+/// A = (A && x) || y becomes A = y || (y && A_tail) and A_tail = x || (x && A_tail).
+/// None if the rules can't be rewritten safely.
+fn rewrite(interp: &Interpreteur, identity: &Identity) -> Option<Vec<String>> {
+    let name = identity.name();
+    let mut tail = format!("{name}_tail");
+    while interp.declared().contains(&tail.as_str()) {
+        tail.push('_');
+    }
+    let mut tails = Forest::new();
+    let mut others = Forest::new();
+    for node in identity.forest().iter() {
+        match node {
+            // A group which can be itself only can't be rewritten
+            Node::Node(tc, false, sons, constraints) if *tc == name && constraints.is_empty() => tails.extend(sons.iter().cloned()),
+            _ if first_names(std::slice::from_ref(node)).contains(&name) => return None,
+            _ => others.push(node.clone())
+        }
+    }
+    if others.is_empty() {
+        return None
+    }
+    Some(vec!(
+        format!("it can be rewritten as right recursion, by declaring the group {tail}:"),
+        format!("{name} = {}", forest_to_rule(&append_forest(&others, &vec!(Node::Leaf(&tail, Vec::new())), true))),
+        format!("{tail} = {}", forest_to_rule(&append_forest(&tails, &vec!(Node::Leaf(&tail, Vec::new())), true))),
+    ))
+}
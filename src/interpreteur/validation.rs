@@ -0,0 +1,183 @@
+use super::include::*;
+use crate::charset::unescape;
+use super::interpreteur::Interpreteur;
+use super::recursion::left_recursions;
+use crate::symbols::SymbolClass;
+use std::collections::HashSet;
+
+/// Check the names used in the rules of an interpreted grammar.
+/// Return the errors, which make the grammar unusable, and the warnings about it.
+pub fn validate(interp: &Interpreteur) -> Vec<Diagnostic> {
+    Validator{interp, used: HashSet::new(), diagnostics: Vec::new()}.run()
+}
+
+struct Validator<'i, 'a> {
+    interp: &'i Interpreteur<'a>,
+    used: HashSet<&'a str>,          // The names used in the rules of another name
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Validator<'_, 'a> {
+
+    fn run(mut self) -> Vec<Diagnostic> {
+        let mut seen = HashSet::new();
+        for name in self.interp.declared().iter() {
+            if !seen.insert(*name) {
+                self.diagnostics.push(self.interp.error(name, format!("{name} is declared twice.")));
+            }
+        }
+        for name in self.interp.declared().iter() {
+            let identity = match self.kind(name) {
+                Some(SymbolClass::Tprim) => &self.interp.token_types()[name],
+                Some(SymbolClass::Group) => &self.interp.group_types()[name],
+                _ => continue
+            };
+            self.check_forest(identity, identity.forest());
+            self.check_constraints(name, self.kind(name), identity.constraints());
+        }
+        self.check_trivia();
+        let start = self.interp.declared().iter().find(|name| self.kind(name) == Some(SymbolClass::Group));
+        let mut seen = HashSet::new();
+        for name in self.interp.declared().iter() {
+            if !seen.insert(*name) {
+                continue;
+            }
+            let kind = self.kind(name).unwrap();
+            let has_rules = match kind {
+                SymbolClass::Char => self.interp.symb_types()[name].is_some(),
+                SymbolClass::Tprim => !self.interp.token_types()[name].forest().is_empty(),
+                SymbolClass::Group => !self.interp.group_types()[name].forest().is_empty(),
+                SymbolClass::Literal | SymbolClass::Trivia => continue     // Never declared
+            };
+            // Without group the input is a sequence of TPRIMs, and the first group is the whole input
+            let root = match kind {
+                SymbolClass::Char => false,
+                SymbolClass::Tprim => start.is_none(),
+                _ => start == Some(name)
+            };
+            if !has_rules {
+                self.diagnostics.push(self.interp.error(name, format!("The {} {name} is declared but has no rule.", kind.describe())));
+            } else if !root && !self.used.contains(name) {
+                self.diagnostics.push(self.interp.error(name, format!("The {} {name} is declared but never used.", kind.describe())).warning());
+            }
+        }
+        self.diagnostics.extend(left_recursions(self.interp));
+        self.diagnostics
+    }
+
+    fn kind(&self, name: &str) -> Option<SymbolClass> {
+        self.interp.symbols().class_of(name)
+    }
+
+    fn check_forest(&mut self, identity: &Identity<'a>, forest: &Forest<'a>) {
+        let in_group = identity.is_group();
+        for node in forest.iter() {
+            if let Node::Repeat(_, body, _, _, sons) = node {
+                self.check_forest(identity, body);
+                self.check_forest(identity, sons);
+                continue;
+            }
+            let name = node.typechar();
+            if name.starts_with('"') {
+                self.check_literal(identity, name);
+            } else {
+                self.check_name(identity, name, in_group);
+                self.check_constraints(name, self.kind(name), node.constraints());
+            }
+            if let Node::Node(_, _, sons, _) = node {
+                self.check_forest(identity, sons);
+            }
+        }
+    }
+
+    /// A string literal matches itself, in a TPRIM as in a GROUP, but it has to match something.
+    fn check_literal(&mut self, identity: &Identity<'a>, literal: &'a str) {
+        match unescape(literal) {
+            Ok(text) if text.is_empty() => self.diagnostics.push(self.interp.error(literal, format!("The empty string used in the rule of {} can't be matched.", identity.name()))),
+            Ok(_) => (),
+            Err((i, message)) => self.diagnostics.push(self.interp.error(&literal[i..], format!("{message} in the rule of {}.", identity.name())))
+        }
+    }
+
+    /// The skipped names have to be CHARs, and the comments can't be empty.
+    fn check_trivia(&mut self) {
+        let trivia = self.interp.trivia();
+        for name in trivia.skip.iter().flatten() {
+            match self.kind(name) {
+                Some(SymbolClass::Char) => {
+                    self.used.insert(name);
+                },
+                _ => self.diagnostics.push(self.interp.error(name, format!("{name} is skipped in the #TRIVIA section but isn't a CHAR.")))
+            }
+        }
+        let strings = trivia.lines.iter().chain(trivia.blocks.iter().flat_map(|(open, close, _)| [open, close]));
+        for string in strings {
+            match unescape(string) {
+                Ok(text) if text.is_empty() => self.diagnostics.push(self.interp.error(string, "The empty string can't delimit a comment.".to_string())),
+                Ok(_) => (),
+                Err((i, message)) => self.diagnostics.push(self.interp.error(&string[i..], format!("{message} in the #TRIVIA section.")))
+            }
+        }
+    }
+
+    /// The constraints of a GROUP can be strings or TPRIMs it has to contain, the others can only be strings.
+    fn check_constraints(&mut self, owner: &'a str, kind: Option<SymbolClass>, constraints: &[&'a str]) {
+        for constraint in constraints.iter() {
+            if constraint.starts_with('"') {
+                if let Err((i, message)) = unescape(constraint) {
+                    self.diagnostics.push(self.interp.error(&constraint[i..], format!("{message} in the constraints of {owner}.")));
+                }
+            } else if kind.is_some_and(|kind| kind != SymbolClass::Group) {
+                self.diagnostics.push(self.interp.error(constraint, format!("The {} {owner} can only be constrained by strings, {constraint} isn't one.", kind.unwrap().describe())));
+            } else if self.kind(constraint) != Some(SymbolClass::Tprim) {
+                self.diagnostics.push(self.interp.error(constraint, format!("{constraint} is used in the constraints of {owner} but isn't a TPRIM.")));
+            } else {
+                self.used.insert(constraint);
+            }
+        }
+    }
+
+    fn check_name(&mut self, identity: &Identity<'a>, name: &'a str, in_group: bool) {
+        match (self.kind(name), in_group) {
+            (None, _) => {
+                let mut message = format!("{name} is used in the rule of {} but has never been declared.", identity.name());
+                if let Some(suggestion) = self.suggest(name, in_group) {
+                    message.push_str(&format!(" Did you mean `{suggestion}`?"));
+                }
+                self.diagnostics.push(self.interp.error(name, message));
+            },
+            (Some(SymbolClass::Char), true) => self.diagnostics.push(self.interp.error(name, format!("The group {} can't be built from the CHAR {name}.", identity.name()))),
+            (Some(SymbolClass::Group), false) => self.diagnostics.push(self.interp.error(name, format!("The primitive token {} can't be built from the GROUP {name}.", identity.name()))),
+            _ => if name != identity.name() {
+                self.used.insert(name);
+            }
+        }
+    }
+
+    /// The declared name closest to an undeclared one among those the rule could use, if it's close enough to be a typo.
+    fn suggest(&self, name: &str, in_group: bool) -> Option<&'a str> {
+        let allowed = if in_group {[SymbolClass::Tprim, SymbolClass::Group]} else {[SymbolClass::Char, SymbolClass::Tprim]};
+        self.interp.declared().iter()
+            .filter(|candidate| allowed.contains(&self.kind(candidate).unwrap()))
+            .map(|candidate| (distance(name, candidate), *candidate))
+            .filter(|(d, candidate)| *d <= candidate.chars().count().max(name.chars().count()) / 3 + 1)
+            .min_by_key(|(d, _)| *d)
+            .map(|(_, candidate)| candidate)
+    }
+}
+
+/// The Levenshtein distance between two names, ignoring the case.
+fn distance(a: &str, b: &str) -> usize {
+    let a = a.to_lowercase().chars().collect::<Vec<_>>();
+    let b = b.to_lowercase().chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec!(i + 1);
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb {0} else {1};
+            current.push((previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,74 @@
+//! Toklang reads grammars written in the .tl language and uses them to tokenize other texts.
+//!
+//! ```no_run
+//! let grammar = toklang::Grammar::from_path("exemple.tl").unwrap();
+//! for lexeme in grammar.tokenize("a || b").unwrap() {
+//!     println!("{} {:?}", lexeme.name, lexeme.content);
+//! }
+//! ```
+
+mod tokenizer;
+mod interpreteur;
+mod engine;
+mod codegen;
+mod error;
+mod diagnostic;
+mod charset;
+mod symbols;
+
+use tokenizer::tokenizer::Tokenizer;
+
+pub use engine::grammar::Grammar;
+pub use engine::engine::Lexemes;
+pub use engine::include::{Lexeme, LexemeKind};
+pub use engine::tree::Tree;
+pub use error::Error;
+pub use diagnostic::{Diagnostic, Position, Level};
+pub use charset::CharSet;
+pub use symbols::{SymbolTable, SymbolClass, TokenKind};
+pub use tokenizer::include::Token;
+use interpreteur::interpreteur::Interpreteur;
+pub use codegen::codegen::CodeGenerator;
+
+/// Write the CHAR rules of the .tl text and the forests built for its other rules, see interpreteur::dump.
+pub fn dump(text: &str) -> Result<String, Error> {
+    Ok(interpreteur::dump::dump(&interpret(text)?))
+}
+
+/// Write the forests built for the rules of the .tl text as a Graphviz graph, see interpreteur::dot.
+pub fn dot(text: &str) -> Result<String, Error> {
+    Ok(interpreteur::dot::dot(&interpret(text)?))
+}
+
+/// Tokenize the .tl text and feed the interpreter with it, every line being interpreted even after an error. The
+/// errors of the lines are reported together, without those of the validation which only runs on a text read entirely.
+pub(crate) fn interpret(text: &str) -> Result<Interpreteur<'_>, Error> {
+    feed(text, Tokenizer::new(text))
+}
+
+/// Interpret the .tl text without checking the syntax of its lines, used to load the meta-grammar toklang.tl.
+pub(crate) fn bootstrap(text: &str) -> Result<Interpreteur<'_>, Error> {
+    feed(text, Tokenizer::bootstrap(text))
+}
+
+fn feed<'a>(text: &'a str, tokenizer: Tokenizer) -> Result<Interpreteur<'a>, Error> {
+    let mut interp = Interpreteur::new(text, tokenizer.symbols());
+    let mut diagnostics = Vec::new();
+    for line in tokenizer {
+        match line {
+            Ok(tokens) => if let Err(diagnostic) = interp.new_line(&tokens) {
+                diagnostics.push(diagnostic)
+            },
+            Err(Error::Grammar(errors)) => diagnostics.extend(errors),
+            Err(e) => return Err(e)
+        }
+    }
+    match diagnostics.last_mut() {
+        None => Ok(interp),
+        Some(last) => {
+            // The rules are only validated once they are all read: the errors of validation would come from the missing lines
+            last.help.push("the rules are validated once every line is read without error".to_string());
+            Err(Error::Grammar(diagnostics))
+        }
+    }
+}
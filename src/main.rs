@@ -1,14 +1,6 @@
 use std::env;
-mod tokenizer;
-mod interpreteur;
-use interpreteur::interpreteur::Interpreteur;
 use std::process::exit;
-use std::thread::spawn;
-use tokenizer::{include::{TokenType, TokenizerMessage, PARSING_ERROR}, tokenizer::Tokenizer};
-use std::sync::mpsc::channel;
-use std::sync::mpsc::Receiver;
-use std::fs::File;
-use std::io::Read;
+use toklang::{Grammar, Tree, Error, CodeGenerator, dump, dot};
 
 fn main() {
     let args = env::args().collect::<Vec<_>>();
@@ -16,43 +8,93 @@ fn main() {
         eprintln!("File path missing");
         exit(1);
     }
-    begin(&args[1]);
+    let res = match args[1].as_str() {
+        "gen" => match args.get(2) {
+            Some(path) => generate(path, args.get(3).map(|output| output.as_str())),
+            None => {
+                eprintln!("File path missing");
+                exit(1);
+            }
+        },
+        "dump" => match args.get(2) {
+            Some(path) => print_forests(path, dump),
+            None => {
+                eprintln!("File path missing");
+                exit(1);
+            }
+        },
+        "dot" => match args.get(2) {
+            Some(path) => print_forests(path, dot),
+            None => {
+                eprintln!("File path missing");
+                exit(1);
+            }
+        },
+        _ => begin(&args[1], args.get(2).map(|input| input.as_str()))
+    };
+    if let Err(e) = res {
+        println!("{e}");
+        exit(1)
+    }
 }
 
-pub fn begin(path: &str) {
-    let mut file: File = match File::open(path) {
-        Ok(f) => f,
-        Err(_) => {
-            eprintln!("The path {} isn't valid.", path);
-            exit(1)
-        }
-    };
-    let mut content = String::new();
-    file.read_to_string(&mut content).expect("Failed to read entry file");
-    let (sender, receiver) = channel::<TokenizerMessage>();
-    let mut interp = Interpreteur::new(&content);
-    let tokenizer = Tokenizer::new(content.clone(), sender);
-    spawn(move ||
-          tokenizer.tokenize_file()
-    );
-    match execute(&mut interp, &receiver) {
-        Ok(_tokenizer) => println!("The execution of the file {} has been a success.", path),
-        Err(e) => println!("Error: {e}")
-    };
+fn read_file(path: &str) -> Result<String, Error> {
+    std::fs::read_to_string(path).map_err(|e| Error::Io(path.to_string(), e))
 }
 
+/// Load the grammar of the file at path and print its warnings.
+fn load(path: &str) -> Result<Grammar, Error> {
+    let grammar = Grammar::from_path(path)?;
+    for warning in grammar.warnings.iter() {
+        eprintln!("{warning}\n");
+    }
+    Ok(grammar)
+}
 
-fn execute(interp: &mut Interpreteur, receiver: &Receiver<TokenizerMessage>) -> Result<(), String> {
-    loop  {
-        match receiver.recv().expect("Something went wrong") {
-            TokenizerMessage::Token(token) =>
-                if token.token_type == TokenType::ERROR {
-                    return Err(PARSING_ERROR.to_string())
-                } else if let Err(e) = interp.new_token(token) {
-                    return Err(e)
-                }
-            TokenizerMessage::End() => break
-        }
+/// Interpret the grammar of the file at path, then use it to tokenize the file at input_path if there is one.
+pub fn begin(path: &str, input_path: Option<&str>) -> Result<(), Error> {
+    let grammar = load(path)?;
+    match input_path {
+        None => println!("The execution of the file {} has been a success.", path),
+        Some(input_path) => run(&grammar, &read_file(input_path)?).map_err(|e| e.in_file(input_path))?
     }
     Ok(())
 }
+
+/// Interpret the grammar of the file at path and write the source of its lexer in output, or on stdout.
+fn generate(path: &str, output: Option<&str>) -> Result<(), Error> {
+    let grammar = load(path)?;
+    let code = CodeGenerator::new(&grammar).map_err(|e| Error::Grammar(vec!(e.in_file(path))))?.generate(path);
+    match output {
+        Some(output) => std::fs::write(output, code).map_err(|e| Error::Io(output.to_string(), e))?,
+        None => print!("{code}")
+    }
+    Ok(())
+}
+
+/// Interpret the grammar of the file at path and print the forests built for its rules, as a text or a DOT graph.
+fn print_forests(path: &str, write: fn(&str) -> Result<String, Error>) -> Result<(), Error> {
+    print!("{}", write(&read_file(path)?).map_err(|e| e.in_file(path))?);
+    Ok(())
+}
+
+/// Tokenize the input with the grammar and print its syntax tree.
+fn run(grammar: &Grammar, input: &str) -> Result<(), Error> {
+    for tree in grammar.tree(input)? {
+        print_tree(&tree, input, 0);
+    }
+    Ok(())
+}
+
+/// Print a node indented by its depth, the lexemes being one step further than the groups.
+fn print_tree(tree: &Tree, input: &str, depth: usize) {
+    let (i, j) = tree.lexeme.content;
+    if tree.is_group() {
+        println!("{}{} [{i}, {j}]", "    ".repeat(depth), tree.name());
+    } else {
+        println!("{}{} [{i}, {j}]: {:?}", "    ".repeat(depth + 1), tree.name(), tree.text(input));
+    }
+    for child in tree.children.iter() {
+        print_tree(child, input, depth + 1);
+    }
+}
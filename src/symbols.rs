@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+/// What a token kind is in the grammar defining it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolClass {
+    Char,
+    Tprim,
+    Group,
+    Literal,    // A string written in a rule
+    Trivia,     // The whitespaces and the comments, when the grammar keeps them
+}
+
+impl SymbolClass {
+    pub fn describe(self) -> &'static str {
+        match self {
+            SymbolClass::Char => "CHAR",
+            SymbolClass::Tprim => "TPRIM",
+            SymbolClass::Group => "GROUP",
+            SymbolClass::Literal => "string",
+            SymbolClass::Trivia => "trivia"
+        }
+    }
+}
+
+/// A kind of token defined by a grammar at runtime. It's only an id in the SymbolTable of the grammar,
+/// comparing two kinds of the same table compares their ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TokenKind(usize);
+
+impl TokenKind {
+    pub fn id(self) -> usize {
+        self.0
+    }
+}
+
+/// The token kinds of a grammar: the names of its #DECLARE section in declaration order, then its strings.
+/// Every name is interned once, the ids are given from 0 in interning order.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    symbols: Vec<(String, SymbolClass)>,
+    ids: HashMap<String, TokenKind>,
+}
+
+impl SymbolTable {
+
+    pub fn new() -> SymbolTable {
+        SymbolTable::default()
+    }
+
+    /// The kind of the name, created if the name has never been interned. A name interned twice keeps its first class.
+    pub fn intern(&mut self, name: &str, class: SymbolClass) -> TokenKind {
+        if let Some(kind) = self.ids.get(name) {
+            return *kind
+        }
+        let kind = TokenKind(self.symbols.len());
+        self.symbols.push((name.to_string(), class));
+        self.ids.insert(name.to_string(), kind);
+        kind
+    }
+
+    pub fn get(&self, name: &str) -> Option<TokenKind> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, kind: TokenKind) -> &str {
+        &self.symbols[kind.0].0
+    }
+
+    pub fn class(&self, kind: TokenKind) -> SymbolClass {
+        self.symbols[kind.0].1
+    }
+
+    /// The class of an interned name.
+    pub fn class_of(&self, name: &str) -> Option<SymbolClass> {
+        self.get(name).map(|kind| self.class(kind))
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Every kind with its name and its class, by id.
+    pub fn iter(&self) -> impl Iterator<Item = (TokenKind, &str, SymbolClass)> {
+        self.symbols.iter().enumerate().map(|(id, (name, class))| (TokenKind(id), name.as_str(), *class))
+    }
+}
@@ -1,3 +1,3 @@
+#[allow(clippy::module_inception)]
 pub mod tokenizer;
-mod grammar_tree;
 pub mod include;
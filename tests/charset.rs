@@ -0,0 +1,88 @@
+use toklang::{Grammar, Error};
+
+/// A grammar with the CHAR rules given, every class being a TPRIM of one char so that it's used.
+fn classes(names: &str, rules: &str) -> String {
+    let tprims = names.split(", ").map(|name| format!("T_{name}")).collect::<Vec<_>>();
+    let tprim_rules = names.split(", ").map(|name| format!("T_{name} = {name}\n")).collect::<String>();
+    format!("#DECLARE\nCHARS = {names}\nTPRIMS = {}\n#CHAR_RULES\n{rules}\n#TPRIM_RULES\n{tprim_rules}", tprims.join(", "))
+}
+
+fn errors(text: &str) -> Vec<String> {
+    match Grammar::parse(text) {
+        Err(Error::Grammar(diagnostics)) => diagnostics.into_iter().filter(|d| d.is_error()).map(|d| d.message).collect(),
+        other => panic!("Grammar errors were expected, got {other:?}")
+    }
+}
+
+#[test]
+fn ranges_and_strings_are_joined() {
+    let grammar = Grammar::parse(&classes("alnum", "alnum = \"a-z\" \"A-F\" \"0-9\" \"_-\"")).unwrap();
+    let set = grammar.char_set("alnum").unwrap();
+    assert!("azAF09_-".chars().all(|c| set.contains(c)));
+    assert!("G ~é".chars().all(|c| !set.contains(c)));
+}
+
+#[test]
+fn escapes_are_read() {
+    let grammar = Grammar::parse(&classes("special", r#"special = "\"\\\n\t\u{3bb}""#)).unwrap();
+    let set = grammar.char_set("special").unwrap();
+    assert!("\"\\\n\tλ".chars().all(|c| set.contains(c)));
+    assert!(!set.contains('n') && !set.contains('u'));
+}
+
+#[test]
+fn negated_classes_contain_the_other_chars() {
+    let grammar = Grammar::parse(&classes("inside", r#"inside = ^"\"\\""#)).unwrap();
+    let set = grammar.char_set("inside").unwrap();
+    assert!(set.is_negated());
+    assert!("a 漢\n".chars().all(|c| set.contains(c)));
+    assert!(!set.contains('"') && !set.contains('\\'));
+    let input = "ab\"";
+    let lexemes = grammar.tokenize(input);
+    assert!(matches!(lexemes, Err(Error::Input(diagnostic)) if diagnostic.position.as_ref().unwrap().offset == 2));
+}
+
+#[test]
+fn invalid_rules_are_reported() {
+    let errors = errors(&classes("a, b, c", "a = \"z-a\"\nb = \"\\q\"\nc = \"\\u{zz}\""));
+    assert_eq!(errors, [
+        "The range z-a is reversed in the rule of a.",
+        "Unknown escape sequence \\q in the rule of b.",
+        "\\u{zz} isn't a valid unicode character in the rule of c."
+    ]);
+}
+
+#[test]
+fn digits_are_decimal() {
+    let grammar = Grammar::parse(&classes("digit", "digit = @DIGITS")).unwrap();
+    let set = grammar.char_set("digit").unwrap();
+    assert!("09٣߀०９𝟘".chars().all(|c| set.contains(c)));
+    assert!("½²Ⅻ①a".chars().all(|c| !set.contains(c)));
+}
+
+#[test]
+fn other_chars_are_neither_letters_nor_digits() {
+    let grammar = Grammar::parse(&classes("other", "other = @OTHER")).unwrap();
+    let set = grammar.char_set("other").unwrap();
+    assert!("+!€«".chars().all(|c| set.contains(c)));
+    assert!("a漢7 \n\t".chars().all(|c| !set.contains(c)));
+}
+
+#[test]
+fn macros_define_classes() {
+    let text = classes("vowel, consonant", "vowel = @VOWEL\nconsonant = ^@VOWEL @DIGITS")
+        .replace("#CHAR_RULES", "#MACROS\nVOWEL = \"aeiou\"\n#CHAR_RULES")
+        .replace("T_vowel = vowel", "T_vowel = vowel+")
+        .replace("T_consonant = consonant", "T_consonant = consonant+");
+    let grammar = Grammar::parse(&text).unwrap();
+    let input = "aei bcd";
+    let lexemes = grammar.tokenize(input).unwrap().iter().map(|lexeme| (lexeme.name, lexeme.text(input))).collect::<Vec<_>>();
+    assert_eq!(lexemes, [("T_vowel", "aei"), ("T_consonant", "bcd")]);
+    assert!(!grammar.char_set("consonant").unwrap().contains('7'));
+}
+
+#[test]
+fn macros_are_defined_before_their_use() {
+    let text = classes("vowel", "vowel = @VOWEL").replace("#TPRIM_RULES", "#MACROS\nVOWEL = \"aeiou\"\n#TPRIM_RULES");
+    assert_eq!(errors(&text), ["The macro @VOWEL doesn't exist in the rule of vowel."]);
+}
@@ -0,0 +1,137 @@
+use toklang::{Grammar, CodeGenerator};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// The main function of the test programs, printing the kind and the text of every token of its argument.
+static MAIN: &str = r#"
+mod lexer;
+
+fn main() {
+    let input = std::env::args().nth(1).unwrap();
+    for token in lexer::Lexer::new(&input) {
+        match token {
+            Ok(token) => println!("{:?} {:?}", token.kind, token.text(&input)),
+            Err(e) => println!("{e}"),
+        }
+    }
+}
+"#;
+
+/// A lexer compiled in a directory of its own, the directory being removed with it.
+struct Program {
+    dir: PathBuf,
+}
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Compile the lexer generated for the grammar in a directory of its own.
+fn compile(grammar: &Grammar, name: &str) -> Program {
+    let program = Program{dir: std::env::temp_dir().join(format!("toklang-{name}-{}", std::process::id()))};
+    std::fs::create_dir_all(&program.dir).unwrap();
+    let code = CodeGenerator::new(grammar).unwrap().generate(name);
+    std::fs::write(program.dir.join("lexer.rs"), code).unwrap();
+    std::fs::write(program.dir.join("main.rs"), MAIN).unwrap();
+    let rustc = std::env::var("RUSTC").unwrap_or("rustc".to_string());
+    let output = Command::new(rustc).current_dir(&program.dir).args(["--edition", "2021", "-o", "lexer", "main.rs"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stderr.is_empty(), "{}", String::from_utf8_lossy(&output.stderr));
+    program
+}
+
+/// The lines printed by the program on the input.
+fn run(program: &Program, input: &str) -> Vec<String> {
+    let output = Command::new(program.dir.join("lexer")).arg(input).output().unwrap();
+    String::from_utf8(output.stdout).unwrap().lines().map(|line| line.to_string()).collect()
+}
+
+#[test]
+fn the_lexer_of_the_example_reads_the_strings_of_the_groups() {
+    let grammar = Grammar::from_path("exemple.tl").unwrap();
+    let program = compile(&grammar, "exemple");
+    assert_eq!(run(&program, "a || (b && c)"), [
+        r#"Ident "a""#, r#"Operator "||""#, r#"LParen "(""#, r#"Ident "b""#, r#"Operator "&&""#, r#"Ident "c""#, r#"RParen ")""#
+    ]);
+    assert_eq!(run(&program, "x{} in 12"), [
+        r#"Ident "x""#, r#"LBrace "{""#, r#"RBrace "}""#, r#"Keyword "in""#, r#"Number "12""#
+    ]);
+}
+
+/// Operators read by priority, Two and Deux matching the same text with the same priority.
+static OPERATORS: &str = "\
+#DECLARE
+CHARS = digit, letter
+TPRIMS = self, Number, Word, Two, Deux
+
+#OPTIONS
+TRIVIA = keep
+
+#PRIORITIES
+self = 1
+Two = 1
+Deux = 1
+
+#TRIVIA
+SKIP in [digit]
+LINE in [\"//\"]
+
+#CHAR_RULES
+digit = \"0\"
+letter = \"a-z\"
+
+#TPRIM_RULES
+self = \"self\"
+Number = \"1\"+
+Word = letter+
+Two = \"2\"
+Deux = \"2\"
+";
+
+#[test]
+fn the_generated_lexer_follows_the_grammar() {
+    let grammar = Grammar::parse(OPERATORS).unwrap();
+    let program = compile(&grammar, "operators");
+    assert_eq!(run(&program, "self0selfish// 2"), [
+        r#"Self_ "self""#, r#"Whitespace "0""#, r#"Self_ "self""#, r#"Word "ish""#, r#"Comment "// 2""#
+    ]);
+    assert_eq!(run(&program, "1001"), [r#"Number "1""#, r#"Whitespace "00""#, r#"Number "1""#]);
+    assert_eq!(run(&program, "ab02"), [r#"Word "ab""#, r#"Whitespace "0""#, "Two and Deux both match the input at byte 3"]);
+    assert_eq!(run(&program, "ab?"), [r#"Word "ab""#, "no token matches the input at byte 2"]);
+}
+
+#[test]
+fn the_variants_are_valid_rust_names() {
+    let text = "#DECLARE\nCHARS = digit\nTPRIMS = _1\n#CHAR_RULES\ndigit = \"0-9\"\n#TPRIM_RULES\n_1 = digit\n";
+    let grammar = Grammar::parse(text).unwrap();
+    assert!(CodeGenerator::new(&grammar).is_err());
+    let text = "#DECLARE\nCHARS = digit\nTPRIMS = a_b, A_B\n#CHAR_RULES\ndigit = \"0-9\"\n#TPRIM_RULES\na_b = digit\nA_B = digit\n";
+    let grammar = Grammar::parse(text).unwrap();
+    let error = CodeGenerator::new(&grammar).err().unwrap();
+    assert_eq!(error.message, "Two tokens would be named AB in the generated lexer.");
+}
+
+#[test]
+fn the_variants_of_other_scripts_are_written_in_ascii() {
+    let text = "\
+#DECLARE
+CHARS = greek, latin
+TPRIMS = Mot_λ, Été
+
+#CHAR_RULES
+greek = \"α-ω\"
+latin = \"a-zé\"
+
+#TPRIM_RULES
+Mot_λ = greek+
+Été = latin+
+";
+    let grammar = Grammar::parse(text).unwrap();
+    let program = compile(&grammar, "scripts");
+    assert_eq!(run(&program, "λαβ été"), [r#"MotU39B "λαβ""#, r#"UC9tUE9 "été""#]);
+    let dir = program.dir.clone();
+    drop(program);
+    assert!(!dir.exists());
+}
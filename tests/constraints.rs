@@ -0,0 +1,84 @@
+use toklang::{Grammar, Error};
+
+/// Statements giving a value to a name, a Name being an Ident which isn't x.
+static STATEMENTS: &str = "\
+#DECLARE
+CHARS = digit, letter
+TPRIMS = Number, Ident
+GROUPS = Stmt, Name, Value, Pair
+
+#CHAR_RULES
+digit = \"0-9\"
+letter = \"a-z\"
+
+#TPRIM_RULES
+Number = digit+
+Ident = letter+
+
+#GROUP_RULES
+Stmt = \"let\" && Name && \"=\" && Value{Number, \"true\"} || \"set\" && Pair{\"a b\"}
+Name = Ident
+Name not in [\"x\"]
+Value = Ident || Number
+Pair = Ident && Ident
+";
+
+fn input_error(grammar: &Grammar, input: &str) -> String {
+    match grammar.tokenize(input) {
+        Err(Error::Input(diagnostic)) => diagnostic.message,
+        other => panic!("An input error was expected, got {other:?}")
+    }
+}
+
+#[test]
+fn groups_constrained_by_their_text() {
+    let grammar = Grammar::parse(STATEMENTS).unwrap();
+    assert!(grammar.tokenize("let y = true").is_ok());
+    assert!(grammar.tokenize("set a b").is_ok());
+    assert_eq!(input_error(&grammar, "set a c"), "Unexpected 'a', expected \"a b\" after \"set\"");
+}
+
+#[test]
+fn groups_constrained_by_the_tprims_they_contain() {
+    let grammar = Grammar::parse(STATEMENTS).unwrap();
+    assert!(grammar.tokenize("let y = 1").is_ok());
+    assert_eq!(input_error(&grammar, "let y = z"), "Unexpected 'z', expected Number or \"true\" after \"=\"");
+}
+
+#[test]
+fn only_tprims_and_strings_constrain_groups() {
+    let text = STATEMENTS.replace("Value{Number, \"true\"}", "Value{Name}");
+    let errors = match Grammar::parse(&text) {
+        Err(Error::Grammar(diagnostics)) => diagnostics.into_iter().map(|d| d.message).collect::<Vec<_>>(),
+        other => panic!("Grammar errors were expected, got {other:?}")
+    };
+    assert_eq!(errors, ["Name is used in the constraints of Value but isn't a TPRIM."]);
+}
+
+#[test]
+fn groups_excluding_texts() {
+    let grammar = Grammar::parse(STATEMENTS).unwrap();
+    assert_eq!(input_error(&grammar, "let x = 1"), "Unexpected 'x', expected Name after \"let\"");
+}
+
+#[test]
+fn tprims_excluding_keywords_need_no_priority() {
+    let text = "\
+#DECLARE
+CHARS = letter
+TPRIMS = Keyword, Ident
+
+#CHAR_RULES
+letter = \"a-z\"
+
+#TPRIM_RULES
+Keyword = letter+
+Keyword in [\"if\", \"else\"]
+Ident = letter+
+Ident not in [\"if\", \"else\"]
+";
+    let grammar = Grammar::parse(text).unwrap();
+    let input = "if iff else elsewhere";
+    let names = grammar.tokenize(input).unwrap().iter().map(|lexeme| lexeme.name).collect::<Vec<_>>();
+    assert_eq!(names, ["Keyword", "Ident", "Keyword", "Ident"]);
+}
@@ -0,0 +1,77 @@
+use toklang::{Grammar, Error, Lexeme, LexemeKind};
+
+/// Sums of numbers and of calls, the arguments of a call being sums too.
+static CALLS: &str = "\
+#DECLARE
+CHARS = digit, letter
+TPRIMS = Number, Ident
+GROUPS = Sum, Value, Call
+
+#CHAR_RULES
+digit = \"0-9\"
+letter = \"a-z\"
+
+#TPRIM_RULES
+Number = digit+
+Ident = letter+
+
+#GROUP_RULES
+Sum = Value{END} && \"+\" && Sum
+Value = Call || Number || Ident
+Call = Ident && \"(\" && Sum && \")\"
+";
+
+fn names<'g>(lexemes: &[Lexeme<'g>]) -> Vec<&'g str> {
+    lexemes.iter().map(|lexeme| lexeme.name).collect()
+}
+
+fn input_error(grammar: &Grammar, input: &str) -> String {
+    match grammar.tokenize(input) {
+        Err(Error::Input(diagnostic)) => diagnostic.message,
+        other => panic!("An input error was expected, got {other:?}")
+    }
+}
+
+#[test]
+fn lexemes_have_byte_spans() {
+    let grammar = Grammar::parse(CALLS).unwrap();
+    let input = "12 + ab";
+    let lexemes = grammar.tokenize(input).unwrap();
+    let spans = lexemes.iter().map(|lexeme| (lexeme.name, lexeme.content)).collect::<Vec<_>>();
+    assert_eq!(spans, [("Sum", (0, 7)), ("Value", (0, 2)), ("Number", (0, 2)), ("\"+\"", (3, 4)), ("Sum", (5, 7)), ("Value", (5, 7)), ("Ident", (5, 7))]);
+    assert_eq!(lexemes[0].kind, LexemeKind::Group);
+    assert_eq!(lexemes[3].kind, LexemeKind::Literal);
+    assert_eq!(lexemes[6].text(input), "ab");
+}
+
+#[test]
+fn groups_contain_the_lexemes_which_follow_them() {
+    let grammar = Grammar::parse(CALLS).unwrap();
+    let lexemes = grammar.tokenize("f(1)").unwrap();
+    assert_eq!(names(&lexemes), ["Sum", "Value", "Call", "Ident", "\"(\"", "Sum", "Value", "Number", "\")\""]);
+    let sizes = lexemes.iter().map(|lexeme| lexeme.size).collect::<Vec<_>>();
+    assert_eq!(sizes, [8, 7, 6, 0, 0, 2, 1, 0, 0]);
+}
+
+#[test]
+fn alternatives_are_tried_after_a_failure() {
+    // Call is tried first on x, then Ident once the ( is missing
+    let grammar = Grammar::parse(CALLS).unwrap();
+    let lexemes = grammar.tokenize("x + g(y + 2)").unwrap();
+    let tprims = lexemes.iter().filter(|lexeme| lexeme.kind != LexemeKind::Group).map(|lexeme| lexeme.name).collect::<Vec<_>>();
+    assert_eq!(tprims, ["Ident", "\"+\"", "Ident", "\"(\"", "Ident", "\"+\"", "Number", "\")\""]);
+}
+
+#[test]
+fn input_errors_say_what_was_expected() {
+    let grammar = Grammar::parse(CALLS).unwrap();
+    assert_eq!(input_error(&grammar, "1 + "), "Unexpected end of input, expected Ident or Number after \"+\"");
+    assert_eq!(input_error(&grammar, "f(1"), "Unexpected end of input, expected \"+\" or \")\" after Number `1`");
+    assert!(input_error(&grammar, "1 ? 2").starts_with("Unexpected '?'"));
+}
+
+#[test]
+fn an_unreadable_grammar_is_a_grammar_error() {
+    assert!(matches!(Grammar::parse("#DECLARE\nTPRIMS = A\n#TPRIM_RULES\nA = B\n"), Err(Error::Grammar(_))));
+    assert!(matches!(Grammar::from_path("nowhere.tl"), Err(Error::Io(_, _))));
+}
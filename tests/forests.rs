@@ -0,0 +1,122 @@
+use toklang::{dump, dot};
+
+/// Words between a quote and a backslash, the quotes and the backslashes having to be escaped in the DOT labels.
+static QUOTED: &str = r#"
+#DECLARE
+CHARS = letter
+TPRIMS = Word
+GROUPS = Quoted
+
+#CHAR_RULES
+letter = "a-z"
+
+#TPRIM_RULES
+Word = letter+
+Word not in ["a\"b", "c\\d"]
+
+#GROUP_RULES
+Quoted = "\"" && Word && "\\"
+"#;
+
+/// Check the graph is a digraph whose strings are all closed and whose braces are balanced outside of them.
+fn well_formed(graph: &str) {
+    assert!(graph.starts_with("digraph grammar {\n") && graph.ends_with("}\n"), "Not a digraph:\n{graph}");
+    let (mut depth, mut quoted, mut escaped) = (0, false, false);
+    for c in graph.chars() {
+        match (quoted, escaped, c) {
+            (true, false, '\\') => escaped = true,
+            (true, true, _) => escaped = false,
+            (_, _, '"') => quoted = !quoted,
+            (false, _, '{') => depth += 1,
+            (false, _, '}') => {
+                assert!(depth > 0, "Unbalanced braces:\n{graph}");
+                depth -= 1
+            },
+            (_, _, '\n') => assert!(!quoted, "A string isn't closed:\n{graph}"),
+            _ => ()
+        }
+    }
+    assert_eq!(depth, 0, "Unbalanced braces:\n{graph}");
+}
+
+#[test]
+fn the_dump_of_the_example() {
+    // The CHAR rules as written, the repetitions, the arguments and the constraints, `in` and `not in`
+    let text = std::fs::read_to_string("exemple.tl").unwrap();
+    assert_eq!(dump(&text).unwrap(), r##"CHAR digit = "0-9"
+CHAR letter = "a-z" "A-Z"
+CHAR symbol = "#=,&|(){}"
+TPRIM Keyword in ["in", "SYMB", "GROUPS", "TPRIM"]
+    letter+  [END]
+TPRIM Operator in ["||", "&&"]
+    symbol  [END]
+        symbol  [END]
+TPRIM Number
+    digit+  [END]
+TPRIM Ident not in ["in", "SYMB", "GROUPS", "TPRIM"]
+    letter  [END]
+        digit  [END]
+            Ident  [END]
+        Ident  [END]
+GROUP Expression
+    Value  [END]
+        Operator
+            Expression  [END]
+        Keyword{"in"}
+            Expression  [END]
+    "("
+        Expression
+            ")"  [END]
+                Operator
+                    Expression  [END]
+GROUP Value
+    Number  [END]
+    Ident  [END]
+        "{"
+            "}"  [END]
+"##);
+}
+
+#[test]
+fn one_cluster_per_forest() {
+    let graph = dot(&std::fs::read_to_string("exemple.tl").unwrap()).unwrap();
+    well_formed(&graph);
+    // The four TPRIMs and the two GROUPs
+    assert_eq!(graph.matches("subgraph cluster_").count(), 6);
+    for label in ["TPRIM Keyword in", "TPRIM Operator in", "TPRIM Number", "TPRIM Ident not in", "GROUP Expression", "GROUP Value"] {
+        assert!(graph.contains(&format!("label=\"{label}")), "No cluster {label}:\n{graph}");
+    }
+}
+
+#[test]
+fn labels_are_escaped() {
+    let graph = dot(QUOTED).unwrap();
+    well_formed(&graph);
+    assert_eq!(graph, r#"digraph grammar {
+    rankdir=LR;
+    node [shape=box, fontname="monospace"];
+
+    subgraph cluster_1 {
+        label="TPRIM Word not in [\"a\\\"b\", \"c\\\\d\"]";
+        rule1 [label="Word", shape=ellipse, style=bold];
+        n0 [label="+", peripheries=2, style=filled, fillcolor=palegreen];
+        rule1 -> n0;
+        n1 [label="letter", peripheries=2, style=filled, fillcolor=palegreen];
+        n0 -> n1 [style=dotted, label="body"];
+    }
+
+    subgraph cluster_2 {
+        label="GROUP Quoted";
+        rule2 [label="Quoted", shape=ellipse, style=bold];
+        n2 [label="\"\\\"\""];
+        rule2 -> n2;
+        n3 [label="Word"];
+        n2 -> n3;
+        n4 [label="\"\\\\\"", peripheries=2, style=filled, fillcolor=palegreen];
+        n3 -> n4;
+    }
+
+    n3 -> rule1 [style=dashed, color=gray, constraint=false];
+}
+"#);
+}
@@ -0,0 +1,80 @@
+use toklang::{Grammar, Error};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Sums of numbers, one per line.
+static SUMS: &str = "\
+#DECLARE
+CHARS = digit
+TPRIMS = Number
+GROUPS = Sum
+
+#CHAR_RULES
+digit = \"0-9\"
+
+#TPRIM_RULES
+Number = digit+
+
+#GROUP_RULES
+Sum = Number && (\"+\" && Number)*
+";
+
+#[test]
+fn lexemes_come_before_the_errors_following_them() {
+    let grammar = Grammar::parse(SUMS).unwrap();
+    let input = "1 + 2\n? 3";
+    let mut lexemes = grammar.lexemes(input);
+    let texts = lexemes.by_ref().take(4).map(|lexeme| lexeme.unwrap().text(input)).collect::<Vec<_>>();
+    assert_eq!(texts, ["1 + 2", "1", "+", "2"]);
+    assert!(matches!(lexemes.next(), Some(Err(Error::Input(_)))));
+    assert!(lexemes.next().is_none());
+    // The eager tokenization only gives the error
+    assert!(grammar.tokenize(input).is_err());
+}
+
+#[test]
+fn only_the_pulled_lexemes_are_matched() {
+    let grammar = Grammar::parse(SUMS).unwrap();
+    let input = "1 + 2\n".repeat(200_000) + "?";
+    let start = Instant::now();
+    let first = grammar.lexemes(&input).take(4).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(first.len(), 4);
+    // Dropping the iterator stops the matching: the error at the end is never reached
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn tokens_keep_the_kinds_of_the_grammar() {
+    let grammar = Grammar::parse(SUMS).unwrap();
+    let tokens = grammar.tokens("1 + 22").collect::<Result<Vec<_>, _>>().unwrap();
+    let kinds = tokens.iter().map(|token| grammar.symbols().name(token.kind)).collect::<Vec<_>>();
+    assert_eq!(kinds, ["Sum", "Number", "\"+\"", "Number"]);
+    assert_eq!(tokens[3].content, (4, 6));
+}
+
+#[test]
+fn spawned_tokens_are_those_of_the_grammar() {
+    let grammar = Arc::new(Grammar::parse(SUMS).unwrap());
+    let input = "1 + 2\n3 + 4";
+    let received = Grammar::spawn(grammar.clone(), input.to_string()).iter().collect::<Result<Vec<_>, _>>().unwrap();
+    let tokens = grammar.tokens(input).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(received, tokens);
+    // The thread stops after the first error
+    let received = Grammar::spawn(grammar, "1 ? 2".to_string()).iter().collect::<Vec<_>>();
+    assert_eq!(received.len(), 3);
+    assert!(matches!(received[2], Err(Error::Input(_))));
+}
+
+#[test]
+fn spawned_tokenizers_stop_with_their_receiver() {
+    let grammar = Arc::new(Grammar::parse(SUMS).unwrap());
+    let receiver = Grammar::spawn(grammar.clone(), "1 + 2\n".repeat(200_000));
+    assert!(receiver.recv().unwrap().is_ok());
+    drop(receiver);
+    // The thread holds the last other reference to the grammar until it stops
+    let start = Instant::now();
+    while Arc::strong_count(&grammar) > 1 {
+        assert!(start.elapsed() < Duration::from_secs(5), "The thread didn't stop");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
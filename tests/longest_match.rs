@@ -0,0 +1,131 @@
+use toklang::{Grammar, Error};
+
+/// Operators sharing their first chars, the declaration order giving the priorities.
+static OPERATORS: &str = "\
+#DECLARE
+CHARS = op
+TPRIMS = Shift, Lt, Arrow, Minus, ShiftAssign, Sym
+
+#OPTIONS
+MATCH = longest
+
+#CHAR_RULES
+op = \"<=>:-\"
+
+#TPRIM_RULES
+Lt = \"<\"
+Shift = \"<<\"
+ShiftAssign = \"<<=\"
+Arrow = \"->\"
+Minus = \"-\"
+Sym = op+
+Sym in [\"::\"]
+";
+
+fn names<'g>(grammar: &'g Grammar, input: &'g str) -> Vec<&'g str> {
+    grammar.tokenize(input).unwrap().iter().map(|lexeme| lexeme.name).collect()
+}
+
+fn input_error(grammar: &Grammar, input: &str) -> String {
+    match grammar.tokenize(input) {
+        Err(Error::Input(diagnostic)) => diagnostic.message,
+        other => panic!("An input error was expected, got {other:?}")
+    }
+}
+
+#[test]
+fn the_longest_match_wins() {
+    let grammar = Grammar::parse(OPERATORS).unwrap();
+    assert_eq!(names(&grammar, "<<= <- -> :: <"), ["ShiftAssign", "Lt", "Minus", "Arrow", "Sym", "Lt"]);
+}
+
+#[test]
+fn the_priority_wins_without_the_option() {
+    let grammar = Grammar::parse(&OPERATORS.replace("MATCH = longest", "")).unwrap();
+    assert_eq!(names(&grammar, "<< -"), ["Shift", "Minus"]);
+    assert!(input_error(&grammar, "<<=").starts_with("Unexpected '='"));
+}
+
+#[test]
+fn the_priority_breaks_the_ties() {
+    let text = "\
+#DECLARE
+CHARS = letter
+TPRIMS = Keyword, Ident, Name
+
+#OPTIONS
+MATCH = longest
+
+#PRIORITIES
+Keyword = 2
+Ident = 1
+Name = 1
+
+#CHAR_RULES
+letter = \"a-z\"
+
+#TPRIM_RULES
+Keyword = letter+
+Keyword in [\"if\"]
+Ident = letter+
+Name = letter+
+Name in [\"iffy\"]
+";
+    let grammar = Grammar::parse(text).unwrap();
+    assert_eq!(names(&grammar, "if iff"), ["Keyword", "Ident"]);
+    assert_eq!(input_error(&grammar, "iffy"), "The input is ambiguous, Ident and Name both match here as long with the same priority");
+}
+
+#[test]
+fn the_listed_priorities_win_over_the_declaration_order() {
+    let text = "\
+#DECLARE
+CHARS = letter
+TPRIMS = Name, Ident, Keyword
+
+#PRIORITIES
+Keyword = 0
+
+#CHAR_RULES
+letter = \"a-z\"
+
+#TPRIM_RULES
+Name = letter+
+Name in [\"x\", \"if\"]
+Ident = letter+
+Keyword = letter+
+Keyword in [\"if\"]
+";
+    let grammar = Grammar::parse(text).unwrap();
+    // Keyword is listed, Name and Ident rank below it in declaration order
+    assert_eq!(names(&grammar, "if x y"), ["Keyword", "Name", "Ident"]);
+}
+
+#[test]
+fn tied_alternatives_are_ambiguous_only_when_both_match() {
+    let text = "\
+#DECLARE
+CHARS = letter
+TPRIMS = Type, Var
+GROUPS = Decl
+
+#PRIORITIES
+Type = 1
+Var = 1
+
+#CHAR_RULES
+letter = \"a-z\"
+
+#TPRIM_RULES
+Type = letter+
+Var = letter+
+
+#GROUP_RULES
+Decl = Type && \":\" || Var && \"=\"
+";
+    let grammar = Grammar::parse(text).unwrap();
+    assert_eq!(names(&grammar, "foo :"), ["Decl", "Type", "\":\""]);
+    assert_eq!(names(&grammar, "foo ="), ["Decl", "Var", "\"=\""]);
+    let grammar = Grammar::parse(&text.replace("Var && \"=\"", "Var && \":\"")).unwrap();
+    assert_eq!(input_error(&grammar, "foo :"), "The input is ambiguous, Type and Var both match here with the same priority");
+}
@@ -0,0 +1,48 @@
+use toklang::{Grammar, Error};
+
+/// Numbers of up to three digits, the lines given being added to the #DECLARE section.
+fn numbers(declare: &str) -> String {
+    format!("#DECLARE\nCHARS = digit\nTPRIMS = Number\n{declare}\n#CHAR_RULES\ndigit = \"0-9\"\n#TPRIM_RULES\nNumber = digit{{,3}}\n")
+}
+
+fn errors(text: &str) -> Vec<String> {
+    match Grammar::parse(text) {
+        Err(Error::Grammar(diagnostics)) => diagnostics.into_iter().map(|d| d.message).collect(),
+        other => panic!("Grammar errors were expected, got {other:?}")
+    }
+}
+
+#[test]
+fn comments_arent_part_of_the_lines() {
+    let grammar = Grammar::parse(&numbers("").replace("CHARS = digit", "CHARS = digit ~ the digits")).unwrap();
+    assert_eq!(grammar.char_names().collect::<Vec<_>>(), ["digit"]);
+}
+
+#[test]
+fn declarations_only_list_names() {
+    assert_eq!(errors(&numbers("GROUPS = 3\nCHARS = \"x\"\nNumber = digit+")), [
+        "Syntax error: unexpected '3', expected Ident after \"=\"",
+        "Syntax error: unexpected '\"', expected Ident after \"=\"",
+        "Syntax error: unexpected '+', expected \",\" after Ident `digit`"
+    ]);
+}
+
+#[test]
+fn repetitions_without_minimum() {
+    let grammar = Grammar::parse(&numbers("")).unwrap();
+    let input = "12345";
+    let texts = grammar.tokenize(input).unwrap().iter().map(|lexeme| lexeme.text(input)).collect::<Vec<_>>();
+    assert_eq!(texts, ["123", "45"]);
+}
+
+#[test]
+fn lines_are_read_with_the_syntax_of_their_section() {
+    let text = numbers("").replace("#TPRIM_RULES", "#PRIORITIES\nNumber = digit\n#TPRIM_RULES");
+    assert_eq!(errors(&text), ["Syntax error: unexpected 'd', expected Number after \"=\""]);
+    // Before the first section only a header is expected, and the lines of a rejected section are ignored
+    let text = format!("CHARS = digit\n#CHARS\nTPRIMS = 3\n{}", numbers(""));
+    assert_eq!(errors(&text), [
+        "Syntax error: unexpected 'C', expected \"#\"",
+        "Syntax error: unexpected 'C', expected \"DECLARE\" or \"OPTIONS\" or \"MACROS\" or \"PRIORITIES\" or \"TRIVIA\" or \"CHAR_RULES\" or \"TPRIM_RULES\" or \"GROUP_RULES\" after \"#\""
+    ]);
+}
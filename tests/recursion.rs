@@ -0,0 +1,51 @@
+use toklang::{Grammar, Error, Diagnostic};
+
+/// A grammar of numbers with the group rules given.
+fn numbers(groups: &str, rules: &str) -> String {
+    format!("#DECLARE\nCHARS = digit\nTPRIMS = Number\nGROUPS = {groups}\n#CHAR_RULES\ndigit = \"0-9\"\n#TPRIM_RULES\nNumber = digit+\n#GROUP_RULES\n{rules}\n")
+}
+
+fn errors(text: &str) -> Vec<Diagnostic> {
+    match Grammar::parse(text) {
+        Err(Error::Grammar(diagnostics)) => diagnostics.into_iter().filter(|d| d.is_error()).collect(),
+        other => panic!("Grammar errors were expected, got {other:?}")
+    }
+}
+
+#[test]
+fn direct_left_recursion_is_rewritten() {
+    let errors = errors(&numbers("Sum", "Sum = Sum && \"+\" && Number || Number"));
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "The group Sum is left recursive: Sum -> Sum.");
+    assert_eq!(errors[0].help, [
+        "it can be rewritten as right recursion, by declaring the group Sum_tail:",
+        "Sum = Number{END} && Sum_tail",
+        "Sum_tail = \"+\" && Number{END} && Sum_tail"
+    ]);
+    // The rewritten rules read the same sums
+    let grammar = Grammar::parse(&numbers("Sum, Sum_tail", &errors[0].help[1..].join("\n"))).unwrap();
+    let input = "1 + 2 + 3";
+    let tprims = grammar.tokenize(input).unwrap().iter().filter(|l| l.name != "Sum" && l.name != "Sum_tail").map(|l| l.text(input)).collect::<Vec<_>>();
+    assert_eq!(tprims, ["1", "+", "2", "+", "3"]);
+}
+
+#[test]
+fn indirect_left_recursion_shows_the_cycle() {
+    let errors = errors(&numbers("Sum, Value", "Sum = Value && \"+\" && Number || Number\nValue = Sum || Number"));
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "The group Sum is left recursive: Sum -> Value -> Sum.");
+    assert!(errors[0].help.is_empty());
+}
+
+#[test]
+fn recursion_after_a_skippable_repetition_is_left_recursion() {
+    let errors = errors(&numbers("Sum, Value", "Sum = Value* && Sum && \"+\" || Number\nValue = Number"));
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "The group Sum is left recursive: Sum -> Sum.");
+    assert!(errors[0].help.is_empty());
+}
+
+#[test]
+fn right_recursion_is_accepted() {
+    assert!(Grammar::parse(&numbers("Sum", "Sum = Number{END} && \"+\" && Sum")).is_ok());
+}
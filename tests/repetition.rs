@@ -0,0 +1,71 @@
+use toklang::{Grammar, LexemeKind};
+
+/// A grammar of words and numbers with the group rule given, a Hex being 0x and one to four hexadecimal digits.
+fn grammar(group: &str, rule: &str) -> Grammar {
+    let text = format!("\
+#DECLARE
+CHARS = digit, hex, letter
+TPRIMS = Hex, Number, Word
+GROUPS = {group}
+
+#PRIORITIES
+Hex = 1
+
+#CHAR_RULES
+digit = \"0-9\"
+hex = \"0-9\" \"a-f\"
+letter = \"a-z\"
+
+#TPRIM_RULES
+Hex = \"0x\" && hex{{1,4}}
+Number = \"-\"? && digit+
+Word = letter+
+
+#GROUP_RULES
+{group} = {rule}
+");
+    let mut grammar = Grammar::parse(&text).unwrap();
+    grammar.warnings.clear();
+    grammar
+}
+
+/// The text of the lexemes which aren't groups, None if the input doesn't match.
+fn texts<'i>(grammar: &Grammar, input: &'i str) -> Option<Vec<&'i str>> {
+    let lexemes = grammar.tokenize(input).ok()?;
+    Some(lexemes.iter().filter(|lexeme| lexeme.kind != LexemeKind::Group).map(|lexeme| lexeme.text(input)).collect())
+}
+
+#[test]
+fn quantifiers_in_tprims() {
+    let grammar = grammar("List", "\"(\" && ((Number || Hex) && \",\")* && \")\"");
+    assert_eq!(texts(&grammar, "(-12, 3, 0x1f,)").unwrap(), ["(", "-12", ",", "3", ",", "0x1f", ",", ")"]);
+    // Hex takes at most four digits
+    assert_eq!(texts(&grammar, "(0xabcde,)"), None);
+}
+
+#[test]
+fn quantifiers_in_groups() {
+    let list = grammar("List", "\"(\" && (Number && \",\")* && Number? && \")\"");
+    assert!(texts(&list, "()").is_some());
+    assert!(texts(&list, "(1, 2)").is_some());
+    assert!(texts(&list, "(1 2)").is_none());
+    let pairs = grammar("Pairs", "(Word && Word){2}");
+    assert!(texts(&pairs, "a b c d").is_some());
+    assert!(texts(&pairs, "a b c").is_none());
+    assert!(texts(&pairs, "a b c d e f").is_none());
+}
+
+#[test]
+fn repetitions_give_back_their_last_matches() {
+    let grammar = grammar("Last", "Word* && Word && Number");
+    assert_eq!(texts(&grammar, "x y 1").unwrap(), ["x", "y", "1"]);
+    assert_eq!(texts(&grammar, "x 1").unwrap(), ["x", "1"]);
+    assert!(texts(&grammar, "1").is_none());
+}
+
+#[test]
+fn long_repetitions_dont_recurse() {
+    let grammar = grammar("List", "\"(\" && (Number && \",\")* && Number? && \")\"");
+    let input = format!("({}1)", "1,".repeat(20000));
+    assert_eq!(texts(&grammar, &input).unwrap().len(), 40003);
+}
@@ -0,0 +1,66 @@
+use toklang::{Grammar, SymbolTable, SymbolClass};
+
+/// Sums of numbers, the trivia being kept.
+static SUMS: &str = "\
+#DECLARE
+CHARS = digit
+TPRIMS = Number
+GROUPS = Sum
+
+#OPTIONS
+TRIVIA = keep
+
+#CHAR_RULES
+digit = \"0-9\"
+
+#TPRIM_RULES
+Number = digit+
+
+#GROUP_RULES
+Sum = Number && (\"+\" && Number)*
+";
+
+#[test]
+fn names_are_interned_once() {
+    let mut symbols = SymbolTable::new();
+    let a = symbols.intern("a", SymbolClass::Char);
+    let b = symbols.intern("b", SymbolClass::Tprim);
+    assert_eq!((a.id(), b.id()), (0, 1));
+    // A name interned again keeps its kind and its first class
+    assert_eq!(symbols.intern("a", SymbolClass::Group), a);
+    assert_eq!(symbols.class(a), SymbolClass::Char);
+    assert_eq!(symbols.len(), 2);
+    assert_eq!((symbols.get("b"), symbols.get("c")), (Some(b), None));
+    assert_eq!(symbols.name(b), "b");
+    assert_eq!(symbols.class_of("b"), Some(SymbolClass::Tprim));
+    let all = symbols.iter().map(|(kind, name, class)| (kind.id(), name, class)).collect::<Vec<_>>();
+    assert_eq!(all, [(0, "a", SymbolClass::Char), (1, "b", SymbolClass::Tprim)]);
+}
+
+#[test]
+fn every_kind_has_its_class() {
+    let grammar = Grammar::parse(SUMS).unwrap();
+    let classes = ["digit", "Number", "Sum", "\"+\"", "Whitespace", "Comment"].map(|name| grammar.symbols().class_of(name));
+    assert_eq!(classes, [SymbolClass::Char, SymbolClass::Tprim, SymbolClass::Group, SymbolClass::Literal, SymbolClass::Trivia, SymbolClass::Trivia].map(Some));
+    assert_eq!(grammar.kind("Number"), grammar.symbols().get("Number"));
+    assert_eq!(grammar.kind("+"), None);
+    // The declared names come first, in declaration order
+    let names = grammar.symbols().iter().take(3).map(|(_, name, _)| name).collect::<Vec<_>>();
+    assert_eq!(names, ["digit", "Number", "Sum"]);
+}
+
+#[test]
+fn tokens_have_the_kinds_of_their_names() {
+    let grammar = Grammar::parse(SUMS).unwrap();
+    let input = "1 + 22";
+    let tokens = grammar.tokens(input).collect::<Result<Vec<_>, _>>().unwrap();
+    let lexemes = grammar.tokenize(input).unwrap();
+    assert_eq!(tokens.len(), lexemes.len());
+    for (token, lexeme) in tokens.iter().zip(lexemes.iter()) {
+        assert_eq!(Some(token.kind), grammar.kind(lexeme.name));
+        assert_eq!(grammar.symbols().name(token.kind), lexeme.name);
+        assert_eq!(token.content, lexeme.content);
+    }
+    let names = tokens.iter().map(|token| grammar.symbols().name(token.kind)).collect::<Vec<_>>();
+    assert_eq!(names, ["Sum", "Number", "Whitespace", "\"+\"", "Whitespace", "Number"]);
+}
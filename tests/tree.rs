@@ -0,0 +1,56 @@
+use toklang::{Grammar, Tree};
+
+/// Sums of products of numbers.
+static SUMS: &str = "\
+#DECLARE
+CHARS = digit
+TPRIMS = Number
+GROUPS = Sum, Product
+
+#CHAR_RULES
+digit = \"0-9\"
+
+#TPRIM_RULES
+Number = digit+
+
+#GROUP_RULES
+Sum = Product{END} && \"+\" && Sum
+Product = Number{END} && \"*\" && Product
+";
+
+/// The tree written with the names of its nodes, the children of a group between parentheses.
+fn shape(tree: &Tree) -> String {
+    match tree.is_group() {
+        true => format!("{}({})", tree.name(), tree.children.iter().map(shape).collect::<Vec<_>>().join(" ")),
+        false => tree.name().to_string()
+    }
+}
+
+#[test]
+fn groups_are_nodes_of_the_tree() {
+    let grammar = Grammar::parse(SUMS).unwrap();
+    let trees = grammar.tree("1 * 2 + 3").unwrap();
+    assert_eq!(trees.len(), 1);
+    assert_eq!(shape(&trees[0]), "Sum(Product(Number \"*\" Product(Number)) \"+\" Sum(Product(Number)))");
+}
+
+#[test]
+fn nodes_span_their_children() {
+    let grammar = Grammar::parse(SUMS).unwrap();
+    let input = "1 * 2 + 3";
+    let trees = grammar.tree(input).unwrap();
+    let product = &trees[0].children[0];
+    assert_eq!(product.lexeme.content, (0, 5));
+    assert_eq!(product.text(input), "1 * 2");
+    let leaves = trees[0].leaves().iter().map(|lexeme| lexeme.text(input)).collect::<Vec<_>>();
+    assert_eq!(leaves, ["1", "*", "2", "+", "3"]);
+}
+
+#[test]
+fn a_grammar_without_groups_gives_leaves() {
+    let text = "#DECLARE\nCHARS = digit\nTPRIMS = Number\n#CHAR_RULES\ndigit = \"0-9\"\n#TPRIM_RULES\nNumber = digit+\n";
+    let grammar = Grammar::parse(text).unwrap();
+    let trees = grammar.tree("1 22").unwrap();
+    assert_eq!(trees.iter().map(shape).collect::<Vec<_>>(), ["Number", "Number"]);
+    assert!(trees.iter().all(|tree| tree.children.is_empty()));
+}
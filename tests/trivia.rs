@@ -0,0 +1,109 @@
+use toklang::{Grammar, Error, LexemeKind};
+
+/// Words separated by spaces and newlines, with shell, ML and C comments, the ML ones nesting.
+static COMMENTS: &str = "\
+#DECLARE
+CHARS = letter, space
+TPRIMS = Word
+
+#TRIVIA
+SKIP in [space]
+LINE in [\"#\"]
+NESTED in [\"(*\", \"*)\"]
+BLOCK in [\"/*\", \"*/\"]
+
+#CHAR_RULES
+letter = \"a-z\"
+space = \" \\n\"
+
+#TPRIM_RULES
+Word = letter+
+";
+
+fn words<'i>(grammar: &Grammar, input: &'i str) -> Vec<&'i str> {
+    grammar.tokenize(input).unwrap().iter().map(|lexeme| lexeme.text(input)).collect()
+}
+
+#[test]
+fn comments_are_skipped() {
+    let grammar = Grammar::parse(COMMENTS).unwrap();
+    assert_eq!(words(&grammar, "a # b c\nd"), ["a", "d"]);
+    assert_eq!(words(&grammar, "a /* b */c"), ["a", "c"]);
+}
+
+#[test]
+fn only_the_nested_comments_nest() {
+    let grammar = Grammar::parse(COMMENTS).unwrap();
+    assert_eq!(words(&grammar, "a (* b (* c *) d *) e"), ["a", "e"]);
+    assert_eq!(words(&grammar, "a /* b /* c */ d"), ["a", "d"]);
+}
+
+#[test]
+fn the_skipped_chars_replace_the_whitespaces() {
+    let grammar = Grammar::parse(COMMENTS).unwrap();
+    assert!(grammar.tokenize("a\tb").is_err());
+    // Without a SKIP line the spaces, tabs and newlines are skipped
+    let grammar = Grammar::parse(&COMMENTS.replace("SKIP in [space]", "").replace("CHARS = letter, space", "CHARS = letter").replace("space = \" \\n\"", "")).unwrap();
+    assert_eq!(words(&grammar, "a\tb\n c"), ["a", "b", "c"]);
+}
+
+#[test]
+fn unclosed_comments_are_input_errors() {
+    let grammar = Grammar::parse(COMMENTS).unwrap();
+    let input = "a (* b (* c *) d";
+    let diagnostic = match grammar.tokenize(input) {
+        Err(Error::Input(diagnostic)) => diagnostic,
+        other => panic!("An input error was expected, got {other:?}")
+    };
+    assert_eq!(diagnostic.message, "This comment is never closed by \"*)\"");
+    assert_eq!(diagnostic.position.unwrap().offset, 2);
+}
+
+/// Lists of words, the trivia being kept.
+static LISTS: &str = "\
+#DECLARE
+CHARS = letter
+TPRIMS = Word
+GROUPS = List, Item
+
+#OPTIONS
+TRIVIA = keep
+
+#TRIVIA
+LINE in [\"//\"]
+BLOCK in [\"/*\", \"*/\"]
+
+#CHAR_RULES
+letter = \"a-z\"
+
+#TPRIM_RULES
+Word = letter+
+
+#GROUP_RULES
+List = \"(\" && (Item && (\",\" && Item)*)? && \")\"
+Item = Word || List
+";
+
+#[test]
+fn kept_trivia_are_lexemes() {
+    let grammar = Grammar::parse(&COMMENTS.replace("#TRIVIA", "#OPTIONS\nTRIVIA = keep\n\n#TRIVIA")).unwrap();
+    let input = "a # b\n(* c *)d";
+    let lexemes = grammar.tokenize(input).unwrap();
+    let names = lexemes.iter().map(|lexeme| (lexeme.name, lexeme.kind == LexemeKind::Trivia, lexeme.text(input))).collect::<Vec<_>>();
+    assert_eq!(names, [
+        ("Word", false, "a"), ("Whitespace", true, " "), ("Comment", true, "# b"), ("Whitespace", true, "\n"), ("Comment", true, "(* c *)"), ("Word", false, "d")
+    ]);
+}
+
+#[test]
+fn kept_trivia_give_back_the_input() {
+    let grammar = Grammar::parse(LISTS).unwrap();
+    let input = " (a, b // c\n\t, /* d */ (e),( ) )\n";
+    let lexemes = grammar.tokenize(input).unwrap();
+    let text = lexemes.iter().filter(|lexeme| lexeme.kind != LexemeKind::Group).map(|lexeme| lexeme.text(input)).collect::<String>();
+    assert_eq!(text, input);
+    // The trivia are in the groups around them
+    let list = grammar.tree(input).unwrap().into_iter().find(|tree| tree.name() == "List").unwrap();
+    assert_eq!(list.text(input), "(a, b // c\n\t, /* d */ (e),( ) )");
+    assert!(list.leaves().iter().any(|lexeme| lexeme.text(input) == "/* d */"));
+}
@@ -0,0 +1,141 @@
+use toklang::{Grammar, Error, LexemeKind, Position};
+
+/// The grammar of the words made of unicode letters, separated by whitespaces.
+static WORDS: &str = "\
+#DECLARE
+CHARS = lettre
+TPRIMS = Mot
+
+#CHAR_RULES
+lettre = @LETTERS
+
+#TPRIM_RULES
+Mot = lettre+
+";
+
+fn words(grammar: &Grammar, input: &str) -> Vec<String> {
+    grammar.tokenize(input).unwrap().iter().map(|lexeme| lexeme.text(input).to_string()).collect()
+}
+
+#[test]
+fn unicode_letters_make_words() {
+    let grammar = Grammar::parse(WORDS).unwrap();
+    assert_eq!(words(&grammar, "été λόγος 漢字"), ["été", "λόγος", "漢字"]);
+}
+
+#[test]
+fn spans_are_in_bytes() {
+    let grammar = Grammar::parse(WORDS).unwrap();
+    let input = "été λ";
+    let spans = grammar.tokenize(input).unwrap().iter().map(|lexeme| lexeme.content).collect::<Vec<_>>();
+    assert_eq!(spans, [(0, 5), (6, 8)]);
+}
+
+#[test]
+fn names_can_be_unicode() {
+    let text = "\
+#DECLARE
+CHARS = chiffre, lettré
+TPRIMS = Nombre, Mot_λ
+
+#CHAR_RULES
+chiffre = \"0-9\"
+lettré = \"a-z\" \"à-ÿ\"
+
+#TPRIM_RULES
+Nombre = chiffre+
+Mot_λ = lettré+
+";
+    let grammar = Grammar::parse(text).unwrap();
+    let lexemes = grammar.tokenize("déjà 42").unwrap();
+    let names = lexemes.iter().map(|lexeme| lexeme.name).collect::<Vec<_>>();
+    assert_eq!(names, ["Mot_λ", "Nombre"]);
+    assert!(lexemes.iter().all(|lexeme| lexeme.kind == LexemeKind::Tprim));
+}
+
+#[test]
+fn ranges_of_multibyte_characters() {
+    let text = "\
+#DECLARE
+CHARS = grec
+TPRIMS = Grec
+
+#CHAR_RULES
+grec = \"α-ω\"
+
+#TPRIM_RULES
+Grec = grec+
+";
+    let grammar = Grammar::parse(text).unwrap();
+    assert_eq!(words(&grammar, "αβγ ω"), ["αβγ", "ω"]);
+    assert!(grammar.tokenize("abc").is_err());
+}
+
+#[test]
+fn syntax_errors_after_multibyte_text() {
+    let text = "\
+~ Grammaire de démonstration: λ, 漢字
+#DECLARE
+CHARS = lettre
+TPRIMS = Mot
+#CHAR_RULES
+lettre = \"a-z\" \"é\"
+#TPRIM_RULES
+Mot = lettre+ (
+";
+    let diagnostics = match Grammar::parse(text) {
+        Err(Error::Grammar(diagnostics)) => diagnostics,
+        other => panic!("A syntax error was expected, got {other:?}")
+    };
+    assert_eq!(diagnostics.len(), 1);
+    let position = diagnostics[0].position.as_ref().unwrap();
+    assert_eq!((position.line, position.column), (8, 15));
+    assert_eq!(&text[position.offset..position.offset + 1], "(");
+}
+
+#[test]
+fn input_errors_have_char_columns() {
+    let grammar = Grammar::parse(WORDS).unwrap();
+    let input = "漢字 été + x";
+    let diagnostic = match grammar.tokenize(input) {
+        Err(Error::Input(diagnostic)) => diagnostic,
+        other => panic!("An input error was expected, got {other:?}")
+    };
+    let position = diagnostic.position.unwrap();
+    assert_eq!(position.offset, input.find('+').unwrap());
+    assert_eq!(position.column, 8);
+}
+
+#[test]
+fn grapheme_columns() {
+    // e followed by a combining acute accent is one grapheme of two chars
+    let text = "e\u{301}e\u{301} x";
+    let position = Position::new(text, text.find('x').unwrap());
+    assert_eq!(position.column, 6);
+    assert_eq!(position.grapheme, 4);
+    // A skin tone and a zero width joiner don't start a grapheme
+    let text = "👍🏽a👨\u{200d}👩x";
+    let position = Position::new(text, text.find('x').unwrap());
+    assert_eq!(position.column, 7);
+    assert_eq!(position.grapheme, 4);
+}
+
+#[test]
+fn rendered_diagnostics_point_at_the_grapheme() {
+    // The letters can be followed by combining marks, written in the grammar as a range
+    let grammar = Grammar::parse(&WORDS.replace("@LETTERS", "@LETTERS \"\u{300}-\u{36f}\"")).unwrap();
+    let input = "漢字\ne\u{301}te\u{301} 漢 + x";
+    let diagnostic = match grammar.tokenize(input) {
+        Err(Error::Input(diagnostic)) => diagnostic,
+        other => panic!("An input error was expected, got {other:?}")
+    };
+    let position = diagnostic.position.as_ref().unwrap();
+    assert_eq!((position.line, position.column, position.grapheme), (2, 9, 7));
+    // The caret is under the '+', the wide character taking two columns
+    assert_eq!(diagnostic.to_string(), "\
+error: Unexpected '+', expected Mot after Mot `漢`
+ --> 2:9
+  |
+2 | e\u{301}te\u{301} 漢 + x
+  |        ^");
+}
@@ -0,0 +1,126 @@
+use toklang::{Grammar, Error, Diagnostic, Level};
+
+fn diagnostics(text: &str) -> Vec<Diagnostic> {
+    match Grammar::parse(text) {
+        Err(Error::Grammar(diagnostics)) => diagnostics,
+        other => panic!("Grammar errors were expected, got {other:?}")
+    }
+}
+
+fn messages(diagnostics: &[Diagnostic], level: Level) -> Vec<&str> {
+    diagnostics.iter().filter(|d| d.level == level).map(|d| d.message.as_str()).collect()
+}
+
+#[test]
+fn undeclared_names_get_suggestions() {
+    let text = "\
+#DECLARE
+CHARS = digit
+TPRIMS = Number
+GROUPS = Sum
+
+#CHAR_RULES
+digit = \"0-9\"
+
+#TPRIM_RULES
+Number = digti+
+
+#GROUP_RULES
+Sum = Numbr && \"+\" && Number || Product
+";
+    let diagnostics = diagnostics(text);
+    assert_eq!(messages(&diagnostics, Level::Error), [
+        "digti is used in the rule of Number but has never been declared. Did you mean `digit`?",
+        "Numbr is used in the rule of Sum but has never been declared. Did you mean `Number`?",
+        "Product is used in the rule of Sum but has never been declared."
+    ]);
+    let position = diagnostics[0].position.as_ref().unwrap();
+    assert_eq!((position.line, position.column), (10, 10));
+}
+
+#[test]
+fn names_without_rules_are_errors() {
+    let text = "\
+#DECLARE
+CHARS = digit, letter
+TPRIMS = Number, Empty
+
+#CHAR_RULES
+digit = \"0-9\"
+
+#TPRIM_RULES
+Number = digit+
+";
+    let diagnostics = diagnostics(text);
+    assert_eq!(messages(&diagnostics, Level::Error), [
+        "The CHAR letter is declared but has no rule.",
+        "The TPRIM Empty is declared but has no rule."
+    ]);
+}
+
+#[test]
+fn unused_names_are_warnings() {
+    let text = "\
+#DECLARE
+CHARS = digit, letter
+TPRIMS = Number, Word, Unused
+GROUPS = Sum
+
+#CHAR_RULES
+digit = \"0-9\"
+letter = \"a-z\"
+
+#TPRIM_RULES
+Number = digit+
+Word = letter+
+Unused = digit
+
+#GROUP_RULES
+Sum = Number && \"+\" && Number
+";
+    let grammar = Grammar::parse(text).unwrap();
+    assert_eq!(messages(&grammar.warnings, Level::Warning), [
+        "The TPRIM Word is declared but never used.",
+        "The TPRIM Unused is declared but never used."
+    ]);
+    assert!(Grammar::from_path("exemple.tl").unwrap().warnings.is_empty());
+}
+
+#[test]
+fn every_bad_line_is_reported() {
+    let text = "\
+#DECLARE
+CHARS = digit
+TPRIMS = Number, Word
+GROUPS = 3
+
+#CHAR_RULES
+digit = \"0-9\"
+
+#TPRIM_RULES
+Number = digit+ (
+Word = digit{2,1}
+";
+    // Number is left without rule, which the validation would report once the lines are fixed
+    assert_eq!(Grammar::parse(text).unwrap_err().to_string(), "\
+error: Syntax error: unexpected '3', expected Ident after \"=\"
+ --> 4:10
+  |
+4 | GROUPS = 3
+  |          ^
+
+error: Syntax error: unexpected '(', expected \"{\" or \"||\" or \"&&\" after \"+\"
+  --> 10:17
+   |
+10 | Number = digit+ (
+   |                 ^
+
+error: The bounds {2,1} of the repetition can't be matched in the rule of Word
+  --> 11:14
+   |
+11 | Word = digit{2,1}
+   |              ^
+   = help: the rules are validated once every line is read without error
+
+error: could not interpret the grammar due to 3 previous errors");
+}